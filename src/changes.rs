@@ -0,0 +1,272 @@
+/* changes against the saved version - hunks of the line diff between that and `lines`, shown as signs in the line
+   number gutter; `next_change`/`prev_change` jump between them, `revert_hunk` undoes one. files in a git repository
+   get a second column against HEAD, see git.rs */
+use crate::{BufferType, EditorBuffer, Editor, Program, RGB, diff::{self, Edit}};
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Sign {
+  Added,
+  Modified,
+  Deleted, // lines were removed below this one (above it on the first line)
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Hunk {
+  pub old: (usize, usize), // (start, length) in the base
+  pub new: (usize, usize), // (start, length) in `lines`
+}
+
+/* hunks of the buffer and the lines they're of; edits re-diff only the region they touched. whatever changes the
+   base (saving and reloading for the saved version, a new HEAD for git's) drops this */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Changes {
+  lines: Vec<String>,
+  base_len: usize, // catches a base changed without dropping this
+  pub hunks: Vec<Hunk>,
+}
+
+pub fn hunks(base: &[String], lines: &[String]) -> Vec<Hunk> {
+  let mut hunks: Vec<Hunk> = vec![];
+  let (mut old, mut new) = (0, 0); // position after the last edit
+  let mut open = false;
+  for edit in diff::diff(base, lines) {
+    match edit {
+      Edit::Equal(o, n) => {
+        open = false;
+        (old, new) = (o + 1, n + 1);
+      },
+      Edit::Delete(_) | Edit::Insert(_) => {
+        if !open {
+          hunks.push(Hunk {old: (old, 0), new: (new, 0)});
+          open = true;
+        }
+        let hunk = hunks.last_mut().unwrap();
+        if let Edit::Delete(_) = edit {
+          hunk.old.1 += 1;
+          old += 1;
+        } else {
+          hunk.new.1 += 1;
+          new += 1;
+        }
+      },
+    }
+  }
+  hunks
+}
+
+/* whether the buffer has a saved version to compare with */
+pub fn tracked(buffer: &EditorBuffer) -> bool {
+  buffer.buf_type == BufferType::File && buffer.large.is_none() && !buffer.save_path.is_empty()
+}
+
+fn saved(buffer: &EditorBuffer) -> Option<&Vec<String>> {
+  if tracked(buffer) {Some(&buffer.old_lines)} else {None}
+}
+
+/* the buffer's hunks against the saved version, kept up to date with the edits since the last call */
+pub fn update(buffer: &mut EditorBuffer) -> &[Hunk] {
+  if !tracked(buffer) {
+    return &[];
+  }
+  cached(&mut buffer.changes, &buffer.old_lines, &buffer.lines)
+}
+
+/* hunks of `lines` against `base` from `cache`, re-diffing only what changed since it was filled */
+pub fn cached<'a>(cache: &'a mut Option<Changes>, base: &[String], lines: &[String]) -> &'a [Hunk] {
+  let changes = match cache.take() {
+    Some(changes) if changes.base_len == base.len() => rediff(changes, base, lines),
+    _ => Changes {lines: lines.to_vec(), base_len: base.len(), hunks: hunks(base, lines)},
+  };
+  &cache.insert(changes).hunks
+}
+
+/* the lines edited since `changes` (between the common prefix and suffix) are re-diffed along with the hunks
+   around them, hunks before stay and the ones after shift */
+fn rediff(mut changes: Changes, base: &[String], lines: &[String]) -> Changes {
+  let old = &changes.lines;
+  let mut prefix = 0;
+  while prefix < old.len() && prefix < lines.len() && old[prefix] == lines[prefix] {
+    prefix += 1;
+  }
+  if prefix == old.len() && prefix == lines.len() {
+    return changes;
+  }
+  let mut suffix = 0;
+  while suffix < old.len() - prefix && suffix < lines.len() - prefix && old[old.len()-1-suffix] == lines[lines.len()-1-suffix] {
+    suffix += 1;
+  }
+  let edit_end = old.len() - suffix;
+  let shift = |x: usize| (x as isize + lines.len() as isize - old.len() as isize) as usize;
+
+  // base and lines are equal between hunks, so the region from the end of the last untouched hunk before the
+  // edit to the start of the first one after it lines up in both
+  let before = changes.hunks.iter().take_while(|x| x.new.0 + x.new.1 < prefix).count();
+  let after = changes.hunks.iter().position(|x| x.new.0 > edit_end).unwrap_or(changes.hunks.len());
+  let (base_start, start) = match before {
+    0 => (0, 0),
+    n => (changes.hunks[n-1].old.0 + changes.hunks[n-1].old.1, changes.hunks[n-1].new.0 + changes.hunks[n-1].new.1),
+  };
+  let (base_end, end) = match changes.hunks.get(after) {
+    Some(x) => (x.old.0, shift(x.new.0)),
+    None => (base.len(), lines.len()),
+  };
+  let mut hunks = changes.hunks[..before].to_vec();
+  hunks.extend(self::hunks(&base[base_start..base_end], &lines[start..end]).into_iter()
+    .map(|x| Hunk {old: (x.old.0 + base_start, x.old.1), new: (x.new.0 + start, x.new.1)}));
+  hunks.extend(changes.hunks[after..].iter().map(|x| Hunk {old: x.old, new: (shift(x.new.0), x.new.1)}));
+  changes.hunks = hunks;
+  changes.lines.splice(prefix..edit_end, lines[prefix..lines.len()-suffix].iter().cloned());
+  changes
+}
+
+/* the sign of every line, for the gutter */
+pub fn signs(hunks: &[Hunk], lines: usize) -> Vec<Option<Sign>> {
+  let mut signs = vec![None; lines];
+  for hunk in hunks {
+    if hunk.new.1 == 0 {
+      if let Some(sign) = signs.get_mut(hunk.new.0.saturating_sub(1)) {
+        sign.get_or_insert(Sign::Deleted);
+      }
+      continue;
+    }
+    for i in 0..hunk.new.1 {
+      signs[hunk.new.0 + i] = Some(if i < hunk.old.1 {Sign::Modified} else {Sign::Added});
+    }
+  }
+  signs
+}
+
+/* one gutter column, colored; `foreground` is the line number color to go back to */
+pub fn gutter(sign: Option<&Option<Sign>>, colors: (RGB, RGB, RGB), foreground: RGB) -> String {
+  match sign {
+    Some(Some(Sign::Added)) => format!("\x1b[38;2;{}m+\x1b[38;2;{foreground}m", colors.0),
+    Some(Some(Sign::Modified)) => format!("\x1b[38;2;{}m~\x1b[38;2;{foreground}m", colors.1),
+    Some(Some(Sign::Deleted)) => format!("\x1b[38;2;{}m_\x1b[38;2;{foreground}m", colors.2),
+    _ => String::from(" "),
+  }
+}
+
+/* the hunk the cursor is in, or whose deletion is marked on the cursor's line */
+pub fn under_cursor(hunks: &[Hunk], y: usize) -> Option<&Hunk> {
+  hunks.iter().find(|x| (x.new.0 <= y && y < x.new.0 + x.new.1) || (x.new.1 == 0 && x.new.0.saturating_sub(1) == y))
+}
+
+pub fn jump(program: &mut Program, forward: bool) {
+  let buffer = program.get_buffer();
+  let y = buffer.cursor.1 as usize;
+  let last = buffer.lines.len() - 1;
+  let hunks = saved(buffer).map(|x| hunks(x, &buffer.lines)).unwrap_or_default();
+  let starts = hunks.iter().map(|x| if x.new.1 == 0 {x.new.0.saturating_sub(1)} else {x.new.0}).collect::<Vec<usize>>();
+  let target = if forward {
+    starts.into_iter().find(|x| *x > y)
+  } else {
+    starts.into_iter().rev().find(|x| *x < y)
+  };
+  match target {
+    Some(line) => {
+      let buffer = program.get_buffer();
+      buffer.cursor = (0, std::cmp::min(line, last) as u32);
+    },
+    None => program.io = String::from(if forward {"no next change"} else {"no previous change"}),
+  }
+}
+
+/* put the saved lines of the hunk under the cursor back */
+pub fn revert(program: &mut Program) {
+  match saved(program.get_buffer()) {
+    Some(base) => {
+      let base = base.clone();
+      revert_to(program, &base);
+    },
+    None => program.io = String::from("no change under the cursor"),
+  }
+}
+
+/* the hunk under the cursor, of the diff against `base`, back to what `base` has */
+pub fn revert_to(program: &mut Program, base: &[String]) {
+  if !program.editable() {
+    return;
+  }
+  let buffer = program.get_buffer();
+  let y = buffer.cursor.1 as usize;
+  let hunk = match under_cursor(&hunks(base, &buffer.lines), y) {
+    Some(h) => h.clone(),
+    None => {
+      program.io = String::from("no change under the cursor");
+      return;
+    }
+  };
+  let old = base[hunk.old.0..hunk.old.0 + hunk.old.1].to_vec();
+  buffer.lines.splice(hunk.new.0..hunk.new.0 + hunk.new.1, old);
+  if buffer.lines.is_empty() {
+    buffer.lines.push(String::new());
+  }
+  buffer.cursor = (0, std::cmp::min(hunk.new.0, buffer.lines.len() - 1) as u32);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines(text: &str) -> Vec<String> {
+    text.split(' ').filter(|x| !x.is_empty()).map(|x| x.to_string()).collect()
+  }
+
+  #[test]
+  fn insertions() {
+    let found = hunks(&lines("a b c"), &lines("a x b c y"));
+    assert_eq!(found, [Hunk {old: (1, 0), new: (1, 1)}, Hunk {old: (3, 0), new: (4, 1)}]);
+    assert_eq!(signs(&found, 5), [None, Some(Sign::Added), None, None, Some(Sign::Added)]);
+  }
+
+  #[test]
+  fn deletions() {
+    let found = hunks(&lines("a b c d"), &lines("b c"));
+    assert_eq!(found, [Hunk {old: (0, 1), new: (0, 0)}, Hunk {old: (3, 1), new: (2, 0)}]);
+    assert_eq!(signs(&found, 2), [Some(Sign::Deleted), Some(Sign::Deleted)]);
+  }
+
+  #[test]
+  fn modifications() {
+    let found = hunks(&lines("a b c"), &lines("a x y c"));
+    assert_eq!(found, [Hunk {old: (1, 1), new: (1, 2)}]);
+    assert_eq!(signs(&found, 4), [None, Some(Sign::Modified), Some(Sign::Added), None]);
+    assert_eq!(under_cursor(&found, 2), Some(&found[0]));
+    assert_eq!(under_cursor(&found, 3), None);
+  }
+
+  #[test]
+  fn empty() {
+    assert_eq!(hunks(&[], &[]), []);
+    let found = hunks(&[], &lines("a b"));
+    assert_eq!(found, [Hunk {old: (0, 0), new: (0, 2)}]);
+    assert_eq!(signs(&found, 2), [Some(Sign::Added), Some(Sign::Added)]);
+    let found = hunks(&lines("a b"), &[]);
+    assert_eq!(found, [Hunk {old: (0, 2), new: (0, 0)}]);
+    assert_eq!(signs(&found, 0), []);
+  }
+
+  #[test]
+  fn rediff_matches_a_full_diff() {
+    let base = lines("a b c d e f g h i j");
+    let mut current = base.clone();
+    let mut changes = Changes {lines: current.clone(), base_len: base.len(), hunks: vec![]};
+    let mut seed: u64 = 7;
+    let mut random = |n: usize| {
+      seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+      (seed >> 33) as usize % n
+    };
+    for step in 0..500 {
+      let at = random(current.len() + 1);
+      match random(3) {
+        0 => current.insert(at, format!("new{}", step)),
+        1 if at < current.len() => {current.remove(at);},
+        _ if at < current.len() => current[at] = format!("edit{}", step),
+        _ => {},
+      }
+      changes = rediff(changes, &base, &current);
+      assert_eq!(changes.hunks, hunks(&base, &current), "step {}: {:?}", step, current);
+      assert_eq!(changes.lines, current);
+    }
+  }
+}
@@ -0,0 +1,163 @@
+/* Tab completion of the IO line in Command state */
+use std::fs;
+use crate::foklang::core::{builtins, AST::{Fructa, NodeKind}};
+use crate::Program;
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Completion {
+  pub candidates: Vec<(String, String)>, // (inserted text, label shown in the menu)
+  pub selected: usize,
+  pub start: usize,                      // where the completed word starts in io
+  pub end: usize,                        // end of the inserted candidate, the io cursor
+}
+
+/* commands whose (string) argument is a path / a buffer index */
+const PATH_COMMANDS: [&str; 16] = ["o", "open", "view", "w", "write", "session_save", "session_load", "touch", "mkdir", "rename", "mv", "copy", "cp", "remove!", "rm!", "tree"];
+const BUFFER_COMMANDS: [&str; 4] = ["b", "setbuf", "bd", "close"];
+
+fn is_word(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '!' || c == '.'
+}
+
+/* Tab / shift+Tab: cycle an open menu, otherwise complete the word before the cursor */
+pub fn complete(program: &mut Program, forward: bool) {
+  if let Some(mut completion) = program.completion.clone() {
+    let len = completion.candidates.len();
+    completion.selected = if forward {(completion.selected + 1) % len} else {(completion.selected + len - 1) % len};
+    apply(program, completion);
+    return;
+  }
+
+  let before = program.io[..program.io_cursor as usize].to_string();
+  let line = before.strip_prefix(":").unwrap_or(&before).to_string();
+  let command = line.split_whitespace().next().unwrap_or("").to_string();
+  let quotes = line.matches('"').count();
+
+  let (start, candidates) = if quotes % 2 == 1 { // inside a string
+    let start = before.rfind('"').unwrap() + 1;
+    let word = &before[start..];
+    if PATH_COMMANDS.contains(&command.as_str()) {
+      (start, paths(word))
+    } else if command == "set" {
+      (start, ["cursor", "buffer"].iter().filter(|x| x.starts_with(word)).map(|x| (x.to_string(), x.to_string())).collect())
+    } else {
+      (start, vec![])
+    }
+  } else {
+    let start = before.char_indices().rev().take_while(|(_, c)| is_word(*c)).last().map(|(i, _)| i).unwrap_or(before.len());
+    let word = &before[start..];
+    let first_word = line[..line.len() - word.len()].trim() == "";
+    if !first_word && word.is_empty() && PATH_COMMANDS.contains(&command.as_str()) {
+      (start, paths("").into_iter().map(|(x, l)| (format!("\"{}", x), l)).collect())
+    } else if !first_word && BUFFER_COMMANDS.contains(&command.as_str()) && word.chars().all(|c| c.is_numeric()) {
+      (start, program.buffers.iter().enumerate().filter(|(i, _)| i.to_string().starts_with(word))
+        .map(|(i, x)| (i.to_string(), format!("{} {}", i, x.buf_name))).collect())
+    } else if word.contains(".") {
+      let (record, key) = word.rsplit_once(".").unwrap();
+      (start + record.len() + 1, keys(program, record).into_iter().filter(|x| x.starts_with(key)).map(|x| (x.clone(), x)).collect())
+    } else {
+      (start, identifiers(program).into_iter().filter(|x| x.starts_with(word)).map(|x| (x.clone(), x)).collect())
+    }
+  };
+
+  if candidates.is_empty() {
+    return;
+  }
+  let single = candidates.len() == 1;
+  let completion = Completion {candidates, selected: 0, start, end: program.io_cursor as usize};
+  apply(program, completion);
+  if single {
+    program.completion = None;
+  }
+}
+
+fn apply(program: &mut Program, completion: Completion) {
+  let text = &completion.candidates[completion.selected].0;
+  program.io = program.io[..completion.start].to_string() + text + &program.io[completion.end..];
+  program.io_cursor = (completion.start + text.len()) as u32;
+  program.completion = Some(Completion {end: completion.start + text.len(), ..completion});
+}
+
+/* everything defined in the foklang environment, builtins and `config` included */
+fn identifiers(program: &Program) -> Vec<String> {
+  let mut names = program.foklang.env.functions.iter().filter_map(|(node, _)| name(node).map(|x| x.to_string())).collect::<Vec<String>>();
+  names.sort();
+  names.dedup();
+  names
+}
+
+fn name(node: &crate::foklang::core::AST::Node) -> Option<&str> {
+  match &node.kind {
+    NodeKind::Identifier{symbol, ..} => Some(symbol),
+    _ => None,
+  }
+}
+
+/* keys of a record, ex. `program.buffer` or `config.ops` - looked up in the environment, never evaluated, so
+   completing can't run a command */
+fn keys(program: &mut Program, record: &str) -> Vec<String> {
+  let mut path = record.split('.');
+  let first = path.next().unwrap_or("");
+  let mut value = if first == "program" && !program.buffers.is_empty() {
+    builtins::program_record(program).value
+  } else {
+    match program.foklang.env.functions.iter().rev().find(|(node, _)| name(node) == Some(first)) {
+      Some((_, value)) => value.value.clone(),
+      None => return vec![],
+    }
+  };
+  for key in path {
+    value = match value {
+      Fructa::Causor(fields) => match fields.into_iter().find(|(node, _)| name(node) == Some(key)) {
+        Some((_, value)) => value.value,
+        None => return vec![],
+      },
+      _ => return vec![],
+    };
+  }
+  match value {
+    Fructa::Causor(fields) => fields.iter().filter_map(|(node, _)| name(node).map(|x| x.to_string())).collect(),
+    _ => vec![],
+  }
+}
+
+fn paths(word: &str) -> Vec<(String, String)> {
+  let (dir, prefix) = match word.rfind('/') {
+    Some(i) => (&word[..i+1], &word[i+1..]),
+    None => ("", word),
+  };
+  let listed = if dir.is_empty() {String::from(".")} else {crate::explorer::expand_home(dir)};
+  let mut entries = match fs::read_dir(listed) {
+    Ok(entries) => entries.filter_map(|x| x.ok()).map(|x| {
+      let name = x.file_name().to_string_lossy().to_string();
+      if x.path().is_dir() {name + "/"} else {name}
+    }).filter(|x| x.starts_with(prefix) && (prefix.starts_with(".") || !x.starts_with("."))).collect::<Vec<String>>(),
+    Err(_) => vec![],
+  };
+  entries.sort();
+  entries.into_iter().map(|x| (format!("{}{}", dir, x), x)).collect()
+}
+
+/* one line menu with the selected candidate highlighted, scrolled to keep it in view */
+pub fn menu(completion: &Completion, width: usize, foreground: crate::RGB, background: crate::RGB) -> String {
+  let labels = completion.candidates.iter().map(|x| format!(" {} ", x.1)).collect::<Vec<String>>();
+  let mut first = 0;
+  while labels[first..=completion.selected].iter().map(|x| x.chars().count()).sum::<usize>() > width && first < completion.selected {
+    first += 1;
+  }
+  let mut result = String::new();
+  let mut used = 0;
+  for (i, label) in labels.iter().enumerate().skip(first) {
+    let len = label.chars().count();
+    if used + len > width {
+      break;
+    }
+    if i == completion.selected {
+      result += &format!("\x1b[38;2;{background}m\x1b[48;2;{foreground}m{label}\x1b[38;2;{foreground}m\x1b[48;2;{background}m");
+    } else {
+      result += label;
+    }
+    used += len;
+  }
+  result + &vec![" "; width - used].into_iter().collect::<String>()
+}
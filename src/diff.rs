@@ -0,0 +1,212 @@
+/* line diff (myers) */
+
+const MAX_COST: usize = 1000; // edit distance past which the differing range is taken as replaced wholesale
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Edit {
+  Equal(usize, usize), // (old index, new index)
+  Delete(usize),       // old index
+  Insert(usize),       // new index
+}
+
+pub fn diff(a: &[String], b: &[String]) -> Vec<Edit> {
+  // common prefix/suffix are free, most edits are small
+  let mut prefix = 0;
+  while prefix < a.len() && prefix < b.len() && a[prefix] == b[prefix] {
+    prefix += 1;
+  }
+  let mut suffix = 0;
+  while suffix < a.len()-prefix && suffix < b.len()-prefix && a[a.len()-1-suffix] == b[b.len()-1-suffix] {
+    suffix += 1;
+  }
+
+  let mut edits = (0..prefix).map(|i| Edit::Equal(i, i)).collect::<Vec<Edit>>();
+  edits.append(&mut myers(&a[prefix..a.len()-suffix], &b[prefix..b.len()-suffix]).into_iter().map(|x| match x {
+    Edit::Equal(i, j) => Edit::Equal(i+prefix, j+prefix),
+    Edit::Delete(i) => Edit::Delete(i+prefix),
+    Edit::Insert(j) => Edit::Insert(j+prefix),
+  }).collect());
+  edits.extend((0..suffix).map(|i| Edit::Equal(a.len()-suffix+i, b.len()-suffix+i)));
+  edits
+}
+
+fn myers(a: &[String], b: &[String]) -> Vec<Edit> {
+  let (n, m) = (a.len() as isize, b.len() as isize);
+  let max = std::cmp::min(a.len() + b.len(), MAX_COST);
+  let offset = max as isize + 1;
+  let mut v = vec![0isize; 2*max + 3];
+  let mut trace: Vec<Vec<isize>> = vec![]; // trace[d] holds diagonals -d-1..=d+1 of `v` before step d
+  let mut found = false;
+
+  'search: for d in 0..=max as isize {
+    trace.push(v[(offset-d-1) as usize..=(offset+d+1) as usize].to_vec());
+    let mut k = -d;
+    while k <= d {
+      let mut x = if k == -d || (k != d && v[(offset+k-1) as usize] < v[(offset+k+1) as usize]) {
+        v[(offset+k+1) as usize]
+      } else {
+        v[(offset+k-1) as usize] + 1
+      };
+      let mut y = x - k;
+      while x < n && y < m && a[x as usize] == b[y as usize] {
+        x += 1;
+        y += 1;
+      }
+      v[(offset+k) as usize] = x;
+      if x >= n && y >= m {
+        found = true;
+        break 'search;
+      }
+      k += 2;
+    }
+  }
+  if !found {
+    // too different to be worth the time, the whole range is one replaced block
+    return (0..a.len()).map(Edit::Delete).chain((0..b.len()).map(Edit::Insert)).collect();
+  }
+
+  // walk the trace back from (n, m)
+  let mut edits = vec![];
+  let (mut x, mut y) = (n, m);
+  for d in (0..trace.len() as isize).rev() {
+    let v = &trace[d as usize];
+    let k = x - y;
+    let prev_k = if k == -d || (k != d && v[(d+k) as usize] < v[(d+k+2) as usize]) {k+1} else {k-1};
+    let prev_x = v[(d+1+prev_k) as usize];
+    let prev_y = prev_x - prev_k;
+    while x > prev_x && y > prev_y {
+      x -= 1;
+      y -= 1;
+      edits.push(Edit::Equal(x as usize, y as usize));
+    }
+    if d > 0 {
+      if x == prev_x {
+        edits.push(Edit::Insert(prev_y as usize));
+      } else {
+        edits.push(Edit::Delete(prev_x as usize));
+      }
+    }
+    x = prev_x;
+    y = prev_y;
+  }
+  edits.reverse();
+  edits
+}
+
+/* `diff -u` style output with `context` unchanged lines around every change */
+pub fn unified(a: &[String], b: &[String], context: usize) -> Vec<String> {
+  let edits = diff(a, b);
+  let changed = edits.iter().map(|x| !matches!(x, Edit::Equal(..))).collect::<Vec<bool>>();
+  let mut result = vec![];
+  let mut i = 0;
+  while i < edits.len() {
+    if !changed[i] {
+      i += 1;
+      continue;
+    }
+    // grow the hunk while the next change is within reach of the context
+    let start = i.saturating_sub(context);
+    let mut end = i;
+    while end < edits.len() && (changed[end] || changed[end..std::cmp::min(end + 2*context + 1, edits.len())].iter().any(|x| *x)) {
+      end += 1;
+    }
+    let end = std::cmp::min(end + context, edits.len());
+    let old_start = edits[start..end].iter().find_map(|x| match x {Edit::Equal(o, _) | Edit::Delete(o) => Some(*o), _ => None}).unwrap_or(0);
+    let new_start = edits[start..end].iter().find_map(|x| match x {Edit::Equal(_, n) | Edit::Insert(n) => Some(*n), _ => None}).unwrap_or(0);
+    let old_len = edits[start..end].iter().filter(|x| !matches!(x, Edit::Insert(_))).count();
+    let new_len = edits[start..end].iter().filter(|x| !matches!(x, Edit::Delete(_))).count();
+    result.push(format!("@@ -{},{} +{},{} @@", old_start+1, old_len, new_start+1, new_len));
+    for edit in &edits[start..end] {
+      result.push(match edit {
+        Edit::Equal(o, _) => format!(" {}", a[*o]),
+        Edit::Delete(o) => format!("-{}", a[*o]),
+        Edit::Insert(n) => format!("+{}", b[*n]),
+      });
+    }
+    i = end;
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines(text: &str) -> Vec<String> {
+    text.split(' ').filter(|x| !x.is_empty()).map(|x| x.to_string()).collect()
+  }
+
+  /* `b` rebuilt from `a` and the edits, and every line of both accounted for in order */
+  fn check(a: &str, b: &str) -> Vec<Edit> {
+    let (a, b) = (lines(a), lines(b));
+    let edits = diff(&a, &b);
+    let (mut old, mut new) = (0, 0);
+    for edit in &edits {
+      match *edit {
+        Edit::Equal(o, n) => {
+          assert_eq!((o, n), (old, new));
+          assert_eq!(a[o], b[n]);
+          (old, new) = (o + 1, n + 1);
+        },
+        Edit::Delete(o) => {
+          assert_eq!(o, old);
+          old += 1;
+        },
+        Edit::Insert(n) => {
+          assert_eq!(n, new);
+          new += 1;
+        },
+      }
+    }
+    assert_eq!((old, new), (a.len(), b.len()));
+    edits
+  }
+
+  #[test]
+  fn equal() {
+    assert_eq!(check("a b c", "a b c"), [Edit::Equal(0, 0), Edit::Equal(1, 1), Edit::Equal(2, 2)]);
+    assert_eq!(check("", ""), []);
+  }
+
+  #[test]
+  fn insertions() {
+    assert_eq!(check("a c", "a b c"), [Edit::Equal(0, 0), Edit::Insert(1), Edit::Equal(1, 2)]);
+    assert_eq!(check("", "a b"), [Edit::Insert(0), Edit::Insert(1)]);
+    assert_eq!(check("a", "a b"), [Edit::Equal(0, 0), Edit::Insert(1)]);
+  }
+
+  #[test]
+  fn deletions() {
+    assert_eq!(check("a b c", "a c"), [Edit::Equal(0, 0), Edit::Delete(1), Edit::Equal(2, 1)]);
+    assert_eq!(check("a b", ""), [Edit::Delete(0), Edit::Delete(1)]);
+  }
+
+  #[test]
+  fn minimal() {
+    let edits = check("a b c a b b a", "c b a b a c");
+    assert_eq!(edits.iter().filter(|x| !matches!(x, Edit::Equal(..))).count(), 5);
+    check("x a y b z", "a q b r");
+  }
+
+  #[test]
+  fn capped() {
+    let a = (0..3000).map(|x| format!("a{}", x)).collect::<Vec<String>>().join(" ");
+    let b = (0..3000).map(|x| format!("b{}", x)).collect::<Vec<String>>().join(" ");
+    let edits = check(&format!("top {} bottom", a), &format!("top {} bottom", b));
+    assert_eq!(edits.len(), 6002); // past MAX_COST, the middle is replaced as a whole
+    assert!(edits[1..3001].iter().all(|x| matches!(x, Edit::Delete(_))));
+    let near = (0..3000).map(|x| if x % 300 == 0 {format!("b{}", x)} else {format!("a{}", x)}).collect::<Vec<String>>().join(" ");
+    assert_eq!(check(&a, &near).iter().filter(|x| !matches!(x, Edit::Equal(..))).count(), 20);
+  }
+
+  #[test]
+  fn unified_hunks() {
+    let a = lines("1 2 3 4 5 6 7 8 9");
+    let mut b = a.clone();
+    b[1] = String::from("two");
+    b.remove(7);
+    assert_eq!(unified(&a, &b, 1), ["@@ -1,3 +1,3 @@", " 1", "-2", "+two", " 3", "@@ -7,3 +7,2 @@", " 7", "-8", " 9"]);
+    assert_eq!(unified(&a, &b, 3)[0], "@@ -1,9 +1,8 @@"); // one hunk when the context meets
+    assert!(unified(&a, &a, 3).is_empty());
+  }
+}
@@ -0,0 +1,200 @@
+/* line endings and text encodings - detected when a file is opened, buffers keep `\n` separated lines
+   and the original format is put back on write */
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum LineEnding {
+  Lf,
+  Crlf,
+  Cr,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum Encoding {
+  Utf8,
+  Utf8Bom,
+  Utf16Le,  // written with a BOM
+  Utf16Be,
+  Latin1,   // anything that isn't valid UTF-8 reads as ISO-8859-1, every byte is a char
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Format {
+  pub line_ending: LineEnding,
+  pub encoding: Encoding,
+}
+impl Default for Format {
+  fn default() -> Self {
+    Self {line_ending: LineEnding::Lf, encoding: Encoding::Utf8}
+  }
+}
+
+impl LineEnding {
+  pub fn name(&self) -> &'static str {
+    match self {
+      LineEnding::Lf => "lf",
+      LineEnding::Crlf => "crlf",
+      LineEnding::Cr => "cr",
+    }
+  }
+  pub fn parse(name: &str) -> Option<LineEnding> {
+    match name.to_lowercase().as_str() {
+      "lf" | "unix" => Some(LineEnding::Lf),
+      "crlf" | "dos" => Some(LineEnding::Crlf),
+      "cr" | "mac" => Some(LineEnding::Cr),
+      _ => None,
+    }
+  }
+  fn text(&self) -> &'static str {
+    match self {
+      LineEnding::Lf => "\n",
+      LineEnding::Crlf => "\r\n",
+      LineEnding::Cr => "\r",
+    }
+  }
+}
+
+impl Encoding {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Encoding::Utf8 => "utf-8",
+      Encoding::Utf8Bom => "utf-8-bom",
+      Encoding::Utf16Le => "utf-16le",
+      Encoding::Utf16Be => "utf-16be",
+      Encoding::Latin1 => "latin-1",
+    }
+  }
+  pub fn parse(name: &str) -> Option<Encoding> {
+    match name.to_lowercase().replace("_", "-").as_str() {
+      "utf-8" | "utf8" => Some(Encoding::Utf8),
+      "utf-8-bom" | "utf8-bom" => Some(Encoding::Utf8Bom),
+      "utf-16le" | "utf-16" | "utf16" => Some(Encoding::Utf16Le),
+      "utf-16be" => Some(Encoding::Utf16Be),
+      "latin-1" | "latin1" | "iso-8859-1" => Some(Encoding::Latin1),
+      _ => None,
+    }
+  }
+}
+
+fn utf16(bytes: &[u8], little_endian: bool) -> String {
+  let units = bytes.chunks(2).map(|x| {
+    let pair = [x[0], *x.get(1).unwrap_or(&0)];
+    if little_endian {u16::from_le_bytes(pair)} else {u16::from_be_bytes(pair)}
+  }).collect::<Vec<u16>>();
+  String::from_utf16_lossy(&units)
+}
+
+/* UTF-16 without a BOM: mostly ASCII text has a zero in every other byte */
+fn utf16_guess(bytes: &[u8]) -> Option<bool> {
+  if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+    return None;
+  }
+  let sample = &bytes[..std::cmp::min(bytes.len(), 4096)];
+  let even = sample.iter().step_by(2).filter(|x| **x == 0).count();
+  let odd = sample.iter().skip(1).step_by(2).filter(|x| **x == 0).count();
+  let half = sample.len() / 2;
+  if odd > half * 3 / 4 && even == 0 {
+    Some(true)
+  } else if even > half * 3 / 4 && odd == 0 {
+    Some(false)
+  } else {
+    None
+  }
+}
+
+/* the line ending used most, CRLF wins ties with LF */
+fn detect_line_ending(text: &str) -> LineEnding {
+  let crlf = text.matches("\r\n").count();
+  let lf = text.matches('\n').count() - crlf;
+  let cr = text.matches('\r').count() - crlf;
+  if crlf > 0 && crlf >= lf && crlf >= cr {
+    LineEnding::Crlf
+  } else if cr > lf {
+    LineEnding::Cr
+  } else {
+    LineEnding::Lf
+  }
+}
+
+pub fn decode(bytes: &[u8]) -> (Vec<String>, Format) {
+  let (text, encoding) = if let Some(text) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).and_then(|x| std::str::from_utf8(x).ok()) {
+    (text.to_string(), Encoding::Utf8Bom)
+  } else if bytes.starts_with(&[0xFF, 0xFE]) {
+    (utf16(&bytes[2..], true), Encoding::Utf16Le)
+  } else if bytes.starts_with(&[0xFE, 0xFF]) {
+    (utf16(&bytes[2..], false), Encoding::Utf16Be)
+  } else if let Ok(text) = std::str::from_utf8(bytes) {
+    (text.to_string(), Encoding::Utf8)
+  } else if let Some(little_endian) = utf16_guess(bytes) {
+    (utf16(bytes, little_endian), if little_endian {Encoding::Utf16Le} else {Encoding::Utf16Be})
+  } else {
+    (bytes.iter().map(|x| *x as char).collect::<String>(), Encoding::Latin1)
+  };
+  let line_ending = detect_line_ending(&text);
+  let lines = match line_ending {
+    LineEnding::Lf => text.split('\n').map(|x| x.to_string()).collect(),
+    LineEnding::Crlf => text.split('\n').map(|x| x.strip_suffix('\r').unwrap_or(x).to_string()).collect(),
+    LineEnding::Cr => text.split('\r').map(|x| x.to_string()).collect(),
+  };
+  (lines, Format {line_ending, encoding})
+}
+
+/* a line as the terminal gets it - control chars (a lone `\r` in an LF file, escapes) would act instead of show, they
+   become `?`, one byte each so columns stay put */
+pub fn printable(line: &str) -> String {
+  line.chars().map(|c| if c.is_ascii_control() && c != '\t' {'?'} else {c}).collect()
+}
+
+pub fn encode(lines: &[String], format: &Format) -> Result<Vec<u8>, String> {
+  let text = lines.join(format.line_ending.text());
+  match format.encoding {
+    Encoding::Utf8 => Ok(text.into_bytes()),
+    Encoding::Utf8Bom => Ok([vec![0xEF, 0xBB, 0xBF], text.into_bytes()].concat()),
+    Encoding::Utf16Le => Ok([0xFF, 0xFE].into_iter().chain(text.encode_utf16().flat_map(|x| x.to_le_bytes())).collect()),
+    Encoding::Utf16Be => Ok([0xFE, 0xFF].into_iter().chain(text.encode_utf16().flat_map(|x| x.to_be_bytes())).collect()),
+    Encoding::Latin1 => text.chars().enumerate().map(|(i, c)| {
+      if (c as u32) < 256 {Ok(c as u8)} else {Err(format!("`{}` (char {}) has no latin-1 encoding, `set_encoding \"utf-8\"` to save it", c, i))}
+    }).collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip(bytes: &[u8], line_ending: LineEnding, encoding: Encoding, lines: &[&str]) {
+    let (decoded, format) = decode(bytes);
+    assert_eq!(decoded, lines);
+    assert_eq!(format, Format {line_ending, encoding});
+    assert_eq!(encode(&decoded, &format).unwrap(), bytes);
+  }
+
+  #[test]
+  fn line_endings() {
+    round_trip(b"a\nb\n", LineEnding::Lf, Encoding::Utf8, &["a", "b", ""]);
+    round_trip(b"a\r\nb\r\n", LineEnding::Crlf, Encoding::Utf8, &["a", "b", ""]);
+    round_trip(b"a\rb\r", LineEnding::Cr, Encoding::Utf8, &["a", "b", ""]);
+    round_trip(b"a\nb\r\nc\n", LineEnding::Lf, Encoding::Utf8, &["a", "b\r", "c", ""]); // a stray CR stays
+  }
+
+  #[test]
+  fn bom() {
+    round_trip(b"\xEF\xBB\xBFz\xC3\xB3\n", LineEnding::Lf, Encoding::Utf8Bom, &["zó", ""]);
+    round_trip(b"\xEF\xBB\xBFa\xFF", LineEnding::Lf, Encoding::Latin1, &["\u{EF}\u{BB}\u{BF}a\u{FF}"]); // not UTF-8 after the BOM
+  }
+
+  #[test]
+  fn utf16() {
+    round_trip(b"\xFF\xFEa\x00\n\x00\xF3\x00", LineEnding::Lf, Encoding::Utf16Le, &["a", "ó"]);
+    round_trip(b"\xFE\xFF\x00a\x00\r\x00\n\x00\xF3", LineEnding::Crlf, Encoding::Utf16Be, &["a", "ó"]);
+    let (lines, format) = decode(b"a\x00\xE9\x00\n\x00c\x00"); // no BOM, written back with one
+    assert_eq!(lines, ["aé", "c"]);
+    assert_eq!(format.encoding, Encoding::Utf16Le);
+  }
+
+  #[test]
+  fn latin1() {
+    round_trip(b"caf\xE9\n", LineEnding::Lf, Encoding::Latin1, &["café", ""]);
+    let format = Format {line_ending: LineEnding::Lf, encoding: Encoding::Latin1};
+    assert!(encode(&[String::from("€")], &format).is_err());
+  }
+}
@@ -0,0 +1,318 @@
+/* directory explorer - listing, entry formatting and opening entries of `Directory` buffers */
+use std::{fs, os::unix::fs::{MetadataExt, PermissionsExt}, path::{Path, PathBuf}};
+use crate::{BufferType, Editor, ExplorerOps, Program, Provider, Subdir, State};
+
+/* sorted listing of `path` with a `..` entry on top */
+pub fn read(path: &str, ops: &ExplorerOps, show_hidden: bool) -> Provider {
+  let path = &fs::canonicalize(path).map(|x| x.to_string_lossy().to_string()).unwrap_or(path.to_string());
+  let mut subdirs = vec![];
+  if let Some(parent) = Path::new(path).parent() {
+    subdirs.push(entry(parent, String::from("..")));
+  }
+  let mut entries = match fs::read_dir(path) {
+    Ok(entries) => entries.filter_map(|x| x.ok())
+      .map(|x| entry(&x.path(), x.file_name().to_string_lossy().to_string()))
+      .filter(|x| show_hidden || !x.name.starts_with("."))
+      .collect::<Vec<Subdir>>(),
+    Err(_) => vec![],
+  };
+  entries.sort_by(|a, b| {
+    let dirs = if ops.dirs_first {b.is_dir.cmp(&a.is_dir)} else {std::cmp::Ordering::Equal};
+    dirs.then(a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+  });
+  subdirs.append(&mut entries);
+  if !subdirs.is_empty() {
+    subdirs[0].selected = true;
+  }
+  Provider {subdirs, selected_index: 0, exit: false, path: path.to_string(), show_hidden}
+}
+
+fn entry(path: &Path, name: String) -> Subdir {
+  let metadata = fs::metadata(path).ok(); // follows symlinks, a link to a directory lists as one
+  Subdir {
+    name,
+    abs_path: path.to_string_lossy().to_string(),
+    selected: false,
+    is_dir: metadata.as_ref().map(|x| x.is_dir()).unwrap_or(false),
+    size: metadata.as_ref().map(|x| x.len()).unwrap_or(0),
+    modified: metadata.as_ref().map(|x| x.mtime()).unwrap_or(0),
+    mode: metadata.as_ref().map(|x| x.permissions().mode()).unwrap_or(0),
+  }
+}
+
+/* `drwxr-xr-x   4.0K 2026-10-19 06:06  name/` */
+pub fn line(subdir: &Subdir) -> String {
+  let kinds = ['r', 'w', 'x'];
+  let mut permissions = String::from(if subdir.is_dir {"d"} else {"-"});
+  for i in (0..9).rev() {
+    permissions.push(if subdir.mode & (1 << i) != 0 {kinds[(8-i) % 3]} else {'-'});
+  }
+  let name = if subdir.is_dir && subdir.name != ".." {subdir.name.clone() + "/"} else {subdir.name.clone()};
+  format!("{} {:>6} {} {}{}", permissions, size(subdir.size), date(subdir.modified), if subdir.selected {"> "} else {"  "}, name)
+}
+
+fn size(bytes: u64) -> String {
+  let units = ["B", "K", "M", "G", "T"];
+  let mut size = bytes as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit < units.len()-1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {format!("{}{}", bytes, units[0])} else {format!("{:.1}{}", size, units[unit])}
+}
+
+pub fn date(time: i64) -> String {
+  unsafe {
+    let mut tm: libc::tm = std::mem::zeroed();
+    libc::localtime_r(&(time as libc::time_t), &mut tm);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min)
+  }
+}
+
+/* re-read the current Directory buffer, keeping the selection on the same name when it's still there */
+pub fn refresh(program: &mut Program, show_hidden: bool) {
+  let provider = match program.get_buffer().buf_type.clone() {
+    BufferType::Directory(d) => d,
+    _ => return,
+  };
+  let selected = provider.subdirs.get(provider.selected_index).map(|x| x.name.clone());
+  let mut provider = read(&provider.path, &program.config.ops.explorer, show_hidden);
+  if let Some(index) = provider.subdirs.iter().position(|x| Some(&x.name) == selected.as_ref()) {
+    provider.subdirs[0].selected = false;
+    provider.subdirs[index].selected = true;
+    provider.selected_index = index;
+  }
+  let buffer = program.get_buffer();
+  buffer.lines = crate::ProviderFn::getlines(&mut provider);
+  buffer.old_lines = buffer.lines.clone();
+  buffer.cursor = (0, provider.selected_index as u32);
+  buffer.buf_type = BufferType::Directory(provider);
+}
+
+/* Enter/Right on an entry: directories replace the listing, files open in their own buffer
+   (the explorer stays open with `ops.explorer.keep_open`) */
+pub fn open_selected(program: &mut Program) {
+  let provider = match program.get_buffer().buf_type.clone() {
+    BufferType::Directory(d) => d,
+    _ => return,
+  };
+  let subdir = match provider.subdirs.get(provider.selected_index) {
+    Some(s) => s.clone(),
+    None => return,
+  };
+  let explorer = program.current;
+  if subdir.is_dir {
+    program.open(subdir.abs_path);
+    if let BufferType::Directory(d) = &program.buffers.last().unwrap().buf_type {
+      if d.show_hidden != provider.show_hidden {
+        program.current = program.buffers.len()-1;
+        refresh(program, provider.show_hidden);
+      }
+    }
+    program.buffers.swap_remove(explorer); // the new listing takes the explorer's place
+    program.current = explorer;
+  } else {
+    let count = program.buffers.len();
+    program.open(subdir.abs_path);
+    if program.buffers.len() == count {
+      return; // couldn't be read, the explorer stays
+    }
+    if !program.config.ops.explorer.keep_open {
+      program.close(explorer);
+    }
+    program.current = program.buffers.len()-1;
+  }
+  program.state = State::Control;
+}
+
+/* switch to the buffer of `path` if it's open already, open it otherwise */
+pub fn open_file(program: &mut Program, path: String) {
+  let canonical = fs::canonicalize(&path).ok();
+  match program.buffers.iter().position(|x| canonical.is_some() && fs::canonicalize(&x.save_path).ok() == canonical) {
+    Some(index) => program.current = index,
+    None => {
+      let count = program.buffers.len();
+      program.open(path);
+      if program.buffers.len() > count {
+        program.current = count;
+      }
+    }
+  }
+}
+
+/* file operations on the explorer of the current buffer; errors end up in io */
+
+fn current_provider(program: &mut Program) -> Option<Provider> {
+  match program.get_buffer().buf_type.clone() {
+    BufferType::Directory(d) => Some(d),
+    _ => {
+      program.io = String::from("not in a directory buffer");
+      None
+    }
+  }
+}
+
+/* a leading `~/` (or a lone `~`) as the home directory */
+pub fn expand_home(path: &str) -> String {
+  match path.strip_prefix("~") {
+    Some(rest) if rest.is_empty() || rest.starts_with("/") => std::env::var("HOME").unwrap_or_default() + rest,
+    _ => path.to_string(),
+  }
+}
+
+/* `name` relative to the listed directory, `~` and absolute paths as they are */
+fn resolve(provider: &Provider, name: &str) -> PathBuf {
+  let name = expand_home(name);
+  if name.starts_with("/") {PathBuf::from(name)} else {Path::new(&provider.path).join(name)}
+}
+
+fn selected(program: &mut Program) -> Option<(Provider, Subdir)> {
+  let provider = current_provider(program)?;
+  match provider.subdirs.get(provider.selected_index) {
+    Some(s) if s.name != ".." => Some((provider.clone(), s.clone())),
+    _ => {
+      program.io = String::from("nothing selected");
+      None
+    }
+  }
+}
+
+/* re-read every explorer buffer */
+pub fn refresh_all(program: &mut Program) {
+  let current = program.current;
+  for i in 0..program.buffers.len() {
+    if let BufferType::Directory(d) = &program.buffers[i].buf_type {
+      let show_hidden = d.show_hidden;
+      program.current = i;
+      refresh(program, show_hidden);
+    }
+  }
+  program.current = current;
+  crate::tree::rebuild(program);
+}
+
+fn report(program: &mut Program, result: std::io::Result<()>, done: String) {
+  program.io = match result {
+    Ok(_) => done,
+    Err(e) => format!("Error: {}", e),
+  };
+  refresh_all(program);
+}
+
+pub fn create(program: &mut Program, name: &str, directory: bool) {
+  let provider = match current_provider(program) {
+    Some(p) => p,
+    None => return,
+  };
+  let path = resolve(&provider, name);
+  let result = if directory {
+    fs::create_dir_all(&path)
+  } else if path.exists() {
+    Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())))
+  } else {
+    path.parent().map(fs::create_dir_all).unwrap_or(Ok(())).and_then(|_| fs::File::create(&path).map(|_| ()))
+  };
+  report(program, result, format!("created {}", path.display()));
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+  if from.is_dir() {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+      let entry = entry?;
+      copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+  } else {
+    fs::copy(from, to).map(|_| ())
+  }
+}
+
+/* moving into an existing directory keeps the name */
+fn destination(provider: &Provider, subdir: &Subdir, name: &str) -> PathBuf {
+  let path = resolve(provider, name);
+  if path.is_dir() {path.join(&subdir.name)} else {path}
+}
+
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+  match fs::rename(from, to) {
+    Err(e) if e.raw_os_error() == Some(libc::EXDEV) => { // other filesystem
+      copy_recursive(from, to)?;
+      if from.is_dir() {fs::remove_dir_all(from)} else {fs::remove_file(from)}
+    },
+    result => result,
+  }
+}
+
+/* open buffers follow their file */
+fn retarget(program: &mut Program, from: &Path, to: &Path) {
+  for buffer in program.buffers.iter_mut() {
+    let path = fs::canonicalize(&buffer.save_path).unwrap_or(PathBuf::from(&buffer.save_path));
+    if let Ok(rest) = path.strip_prefix(from) {
+      let new = if rest.as_os_str().is_empty() {to.to_path_buf()} else {to.join(rest)};
+      buffer.save_path = new.to_string_lossy().to_string();
+      buffer.buf_name = buffer.save_path.clone();
+    }
+  }
+}
+
+pub fn move_selected(program: &mut Program, name: &str, copy: bool) {
+  let (provider, subdir) = match selected(program) {
+    Some(s) => s,
+    None => return,
+  };
+  let from = PathBuf::from(&subdir.abs_path);
+  let to = destination(&provider, &subdir, name);
+  if to.exists() {
+    program.io = format!("{} already exists", to.display());
+    return;
+  }
+  // `to` doesn't exist yet, its parent does
+  let inside = fs::canonicalize(&from).ok().zip(to.parent().and_then(|x| fs::canonicalize(x).ok()))
+    .map(|(from, parent)| parent.starts_with(from)).unwrap_or(false);
+  if inside {
+    program.io = format!("can't {} {} into itself", if copy {"copy"} else {"move"}, subdir.name);
+    return;
+  }
+  if copy {
+    let result = copy_recursive(&from, &to);
+    report(program, result, format!("copied to {}", to.display()));
+  } else {
+    let result = move_path(&from, &to);
+    if result.is_ok() {
+      retarget(program, &from, &to);
+    }
+    report(program, result, format!("moved to {}", to.display()));
+  }
+}
+
+/* asks first, `y` deletes it */
+pub fn delete_selected(program: &mut Program) {
+  let (_, subdir) = match selected(program) {
+    Some(s) => s,
+    None => return,
+  };
+  let question = format!("delete {}{}? [y/n]", subdir.name, if subdir.is_dir {"/ and everything in it"} else {""});
+  program.confirm = Some((question.clone(), PathBuf::from(subdir.abs_path)));
+  program.io = question;
+}
+
+pub fn delete(program: &mut Program, path: &Path) {
+  let trash = expand_home(&program.config.ops.explorer.trash);
+  let result = if trash != String::new() {
+    let name = path.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+    let mut target = Path::new(&trash).join(&name);
+    let mut n = 1;
+    while target.exists() {
+      target = Path::new(&trash).join(format!("{}.{}", name, n));
+      n += 1;
+    }
+    fs::create_dir_all(&trash).and_then(|_| move_path(path, &target))
+  } else if path.is_dir() {
+    fs::remove_dir_all(path)
+  } else {
+    fs::remove_file(path)
+  };
+  report(program, result, format!("{} {}", if trash != String::new() {"trashed"} else {"deleted"}, path.display()));
+}
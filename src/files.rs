@@ -0,0 +1,86 @@
+/* saving files to disk - never leaves a half written file behind */
+use std::{fs, io::Write, os::unix::fs::MetadataExt, path::{Path, PathBuf}};
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum Backup {
+  None,
+  Tilde,      // file.txt~, overwritten on every save
+  Timestamp,  // file.txt.20260101-120000~, one per save
+}
+
+/* whether the user may write to the existing file `path` */
+pub fn writable(path: &str) -> bool {
+  match std::ffi::CString::new(path) {
+    Ok(p) => unsafe { libc::access(p.as_ptr(), libc::W_OK) == 0 },
+    Err(_) => false,
+  }
+}
+
+/* writes `contents` to a temporary file next to `path` and renames it into place */
+pub fn save(path: &str, contents: &str, backup: &Backup) -> Result<(), String> {
+  save_bytes(path, contents.as_bytes(), backup)
+}
+
+pub fn save_bytes(path: &str, contents: &[u8], backup: &Backup) -> Result<(), String> {
+  // write through symlinks instead of replacing them
+  let target = match fs::canonicalize(path) {
+    Ok(p) => p,
+    Err(_) => PathBuf::from(path),
+  };
+  let metadata = fs::metadata(&target).ok();
+  let dir = match target.parent() {
+    Some(p) if p != Path::new("") => p.to_path_buf(),
+    _ => PathBuf::from("."),
+  };
+  let name = target.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+  let tmp = dir.join(format!(".{}.{}.fokedit-tmp", name, std::process::id()));
+
+  let result = (|| -> std::io::Result<()> {
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    if let Some(m) = &metadata {
+      file.set_permissions(m.permissions())?;
+      // keeping the owner only works for root or when nothing changes, not worth failing the save over
+      unsafe { libc::fchown(std::os::unix::io::AsRawFd::as_raw_fd(&file), m.uid(), m.gid()); }
+    }
+    file.sync_all()?;
+    Ok(())
+  })();
+  if let Err(e) = result {
+    let _ = fs::remove_file(&tmp);
+    return Err(format!("couldn't write {}: {}", path, e));
+  }
+
+  if metadata.is_some() {
+    if let Err(e) = make_backup(&target, backup) {
+      let _ = fs::remove_file(&tmp);
+      return Err(format!("couldn't back up {}: {}", path, e));
+    }
+  }
+  if let Err(e) = fs::rename(&tmp, &target) {
+    let _ = fs::remove_file(&tmp);
+    return Err(format!("couldn't write {}: {}", path, e));
+  }
+  Ok(())
+}
+
+fn make_backup(target: &Path, backup: &Backup) -> std::io::Result<()> {
+  let name = target.to_string_lossy().to_string();
+  let backup_path = match backup {
+    Backup::None => return Ok(()),
+    Backup::Tilde => name + "~",
+    Backup::Timestamp => format!("{}.{}~", name, timestamp()),
+  };
+  fs::copy(target, backup_path)?;
+  Ok(())
+}
+
+/* local time as YYYYMMDD-HHMMSS */
+fn timestamp() -> String {
+  unsafe {
+    let now = libc::time(std::ptr::null_mut());
+    let mut tm: libc::tm = std::mem::zeroed();
+    libc::localtime_r(&now, &mut tm);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec)
+  }
+}
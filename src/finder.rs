@@ -0,0 +1,184 @@
+/* fuzzy file finder - `find` lists the project's files (see ignore.rs), typing narrows them down,
+   Enter opens the highlighted one */
+use std::fs;
+use crate::{KeyCode, KeyEvent, Direction, Modifier, Program, RGB};
+
+const PREVIEW_BYTES: usize = 64 * 1024;
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Finder {
+  pub query: String,
+  pub files: Vec<String>,
+  pub matches: Vec<(i32, usize, Vec<usize>)>, // (score, index in files, matched char positions), best first
+  pub selected: usize,
+  pub offset: usize,
+  pub preview: (usize, Vec<String>),          // (index in files, first lines) of the highlighted file
+}
+
+/* subsequence match of `query` in `text` (case insensitive), None if some char is missing.
+   consecutive chars, chars starting a word and chars in the file name score more, gaps cost */
+pub fn score(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+  let query = query.to_lowercase().chars().filter(|c| *c != ' ').collect::<Vec<char>>();
+  let text = text.chars().collect::<Vec<char>>();
+  let lower = text.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect::<Vec<char>>();
+  let name_start = text.iter().rposition(|c| *c == '/').map(|x| x+1).unwrap_or(0);
+
+  // the rightmost occurrence of the subsequence, which keeps matches in the file name and close together
+  let mut positions = vec![0; query.len()];
+  let mut i = text.len();
+  for (q, c) in query.iter().enumerate().rev() {
+    i = lower[..i].iter().rposition(|x| x == c)?;
+    positions[q] = i;
+  }
+
+  let mut score = 0;
+  for (q, &p) in positions.iter().enumerate() {
+    score += 16;
+    if p == 0 || ['/', '_', '-', '.', ' '].contains(&text[p-1]) || (text[p].is_uppercase() && text[p-1].is_lowercase()) {
+      score += 10;
+    }
+    if p >= name_start {
+      score += 4;
+    }
+    if q > 0 {
+      let gap = p - positions[q-1] - 1;
+      score += if gap == 0 {8} else {-(std::cmp::min(gap, 10) as i32)};
+    }
+  }
+  Some((score - text.len() as i32 / 8, positions))
+}
+
+pub fn start(program: &mut Program) {
+  let files = crate::ignore::walk(".");
+  program.finder = Some(Finder {query: String::new(), files, matches: vec![], selected: 0, offset: 0, preview: (usize::MAX, vec![])});
+  update(program);
+}
+
+fn update(program: &mut Program) {
+  let finder = program.finder.as_mut().unwrap();
+  finder.matches = finder.files.iter().enumerate()
+    .filter_map(|(i, file)| score(&finder.query, file).map(|(s, positions)| (s, i, positions)))
+    .collect();
+  finder.matches.sort_by(|a, b| b.0.cmp(&a.0).then(finder.files[a.1].len().cmp(&finder.files[b.1].len())));
+  finder.selected = 0;
+  finder.offset = 0;
+  program.io = format!("find: {}", finder.query);
+  program.io_cursor = program.io.len() as u32;
+}
+
+fn preview(path: &str) -> Vec<String> {
+  let mut bytes = match fs::File::open(path) {
+    Ok(file) => {
+      let mut bytes = vec![];
+      let _ = std::io::Read::read_to_end(&mut std::io::Read::take(file, PREVIEW_BYTES as u64), &mut bytes);
+      bytes
+    },
+    Err(e) => return vec![e.to_string()],
+  };
+  if bytes.contains(&0) {
+    return vec![String::from("(binary file)")];
+  }
+  bytes.truncate(PREVIEW_BYTES);
+  String::from_utf8_lossy(&bytes).replace("\t", "    ").lines().map(|x| x.to_string()).collect()
+}
+
+pub fn key(program: &mut Program, event: KeyEvent) {
+  let finder = program.finder.as_mut().unwrap();
+  let ctrl = event.modifiers == vec![Modifier::Control];
+  match event.code {
+    KeyCode::Escape => {
+      program.finder = None;
+      program.io = String::new();
+      program.io_cursor = 0;
+    },
+    KeyCode::Char('c') | KeyCode::Char('g') if ctrl => {
+      program.finder = None;
+      program.io = String::new();
+      program.io_cursor = 0;
+    },
+    KeyCode::Arrow(Direction::Up) => finder.selected = finder.selected.saturating_sub(1),
+    KeyCode::Char('p') if ctrl => finder.selected = finder.selected.saturating_sub(1),
+    KeyCode::Arrow(Direction::Down) => finder.selected = std::cmp::min(finder.selected+1, finder.matches.len().saturating_sub(1)),
+    KeyCode::Char('n') if ctrl => finder.selected = std::cmp::min(finder.selected+1, finder.matches.len().saturating_sub(1)),
+    KeyCode::Enter => {
+      let file = finder.matches.get(finder.selected).map(|x| finder.files[x.1].clone());
+      program.finder = None;
+      program.io = String::new();
+      program.io_cursor = 0;
+      if let Some(file) = file {
+        crate::explorer::open_file(program, file);
+      }
+    },
+    KeyCode::Backspace => {
+      finder.query.pop();
+      update(program);
+    },
+    KeyCode::Char(c) if event.modifiers.is_empty() || event.modifiers == vec![Modifier::Shift] => {
+      finder.query.push(c);
+      update(program);
+    },
+    KeyCode::Colon => {
+      finder.query.push(':');
+      update(program);
+    },
+    _ => {}
+  }
+}
+
+/* matches on the left, preview of the highlighted file on the right; `rows` lines of `width` columns */
+pub fn render(program: &mut Program, rows: usize, width: usize) -> Vec<String> {
+  let colors = program.config.colors.clone();
+  let finder = program.finder.as_mut().unwrap();
+  if finder.selected < finder.offset {
+    finder.offset = finder.selected;
+  } else if finder.selected >= finder.offset + rows.saturating_sub(1) {
+    finder.offset = finder.selected + 2 - rows;
+  }
+  let list_width = if width >= 60 {width / 2} else {width};
+  let preview_width = width - list_width;
+  if let Some(selected) = finder.matches.get(finder.selected) {
+    if finder.preview.0 != selected.1 {
+      finder.preview = (selected.1, preview(&finder.files[selected.1]));
+    }
+  }
+
+  let (foreground, background, highlight): (RGB, RGB, RGB) = (colors.foreground, colors.background, colors.directory);
+  let mut result = vec![];
+  for row in 0..rows {
+    let mut line = format!("\x1b[38;2;{foreground}m\x1b[48;2;{background}m");
+    let mut used = 0;
+    if row == 0 {
+      let header = format!(" {}/{} files", finder.matches.len(), finder.files.len());
+      line += &header.chars().take(list_width).collect::<String>();
+      used = std::cmp::min(header.chars().count(), list_width);
+    } else if let Some((_, index, positions)) = finder.matches.get(finder.offset + row - 1) {
+      let selected = finder.offset + row - 1 == finder.selected;
+      if selected {
+        line += &format!("\x1b[48;2;{}m", colors.selection_color);
+      }
+      line += if selected {"> "} else {"  "};
+      used = 2;
+      for (i, c) in finder.files[*index].chars().enumerate() {
+        if used >= list_width {
+          break;
+        }
+        if positions.contains(&i) {
+          line += &format!("\x1b[38;2;{highlight}m{c}\x1b[38;2;{foreground}m");
+        } else {
+          line.push(c);
+        }
+        used += 1;
+      }
+    }
+    line += &" ".repeat(list_width.saturating_sub(used));
+    line += &format!("\x1b[38;2;{foreground}m\x1b[48;2;{background}m");
+    if preview_width > 0 {
+      let text = finder.preview.1.get(row).map(|x| x.chars().take(preview_width - 2).collect::<String>()).unwrap_or_default();
+      let border = colors.border;
+      line += &format!("\x1b[38;2;{border}m│\x1b[38;2;{foreground}m {}", text);
+      line += &" ".repeat(preview_width - 2 - text.chars().count());
+    }
+    result.push(line + "\n");
+  }
+  result
+}
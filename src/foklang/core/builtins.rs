@@ -2,7 +2,7 @@ use crate::foklang::core::AST::{*};
 use crate::foklang::core::env::Environment;
 use crate::foklang::core::interpreter::Interpreter;
 
-use crate::{ColorConfig, Editor, ElementsConfig, FokEditConfig, KeyEvent, Program, State, RGB};
+use crate::{BufferType, ColorConfig, Editor, ElementsConfig, FokEditConfig, KeyEvent, Program, State, RGB};
 
 use std::{process::Command, env, str, fs, collections::HashMap}; // TEMPORARY SOLUTION
 
@@ -374,7 +374,71 @@ pub fn read_file(arguments: Arguments) -> Proventus {
 
 /// PROGRAM 
 
+/* refuses while any buffer has unsaved changes, listing them */
+fn try_quit(program: &mut Program) {
+  let modified = program.buffers.iter().filter(|x| x.modified()).map(|x| x.buf_name.clone()).collect::<Vec<String>>();
+  if !modified.is_empty() {
+    program.io = format!("unsaved changes in: {} (`q!` to quit anyway)", modified.join(", "));
+  } else {
+    program.exit = true;
+  }
+}
+
+/* writes buffer `index` to its save_path, reporting failures into io; unless `force`d it won't overwrite a file changed on disk since */
+fn save_buffer(program: &mut Program, index: usize, force: bool) -> bool {
+  let backup = program.config.ops.backup.clone();
+  let buffer = &mut program.buffers[index];
+  if buffer.save_path == String::new() {
+    program.io = String::from("filename not provided!");
+    return false;
+  }
+  if buffer.large.is_some() {
+    program.io = format!("{} is a large file, only a window of it is loaded", buffer.buf_name);
+    return false;
+  }
+  if buffer.read_only {
+    program.io = format!("{} is read-only", buffer.buf_name);
+    return false;
+  }
+  if let Some(conflict) = crate::watch::conflict(buffer).filter(|_| !force) {
+    program.io = conflict;
+    return false;
+  }
+  let bytes = match buffer.contents() {
+    Ok(b) => b,
+    Err(e) => {
+      program.io = e;
+      return false;
+    }
+  };
+  match crate::files::save_bytes(&buffer.save_path, &bytes, &backup) {
+    Ok(_) => {
+      buffer.old_lines = buffer.lines.clone();
+      buffer.changes = None;
+      buffer.saved_format = buffer.format.clone();
+      buffer.disk = crate::watch::stamp(&buffer.save_path, &bytes);
+      program.io = String::from("Saved!");
+      true
+    },
+    Err(e) => {
+      program.io = e;
+      false
+    }
+  }
+}
+
 pub fn quit(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      try_quit(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn quit_force(arguments: Arguments) -> Proventus {
   match arguments.function {
     FunctionArgs::zerumProgram(program) => {
       let mut program = program;
@@ -385,34 +449,107 @@ pub fn quit(arguments: Arguments) -> Proventus {
   }
 }
 
+/* w! - write even if the file changed on disk since it was read */
+pub fn write_force(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      let current = program.current;
+      save_buffer(&mut program, current, true);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
 pub fn write(arguments: Arguments) -> Proventus {
   match arguments.function {
     FunctionArgs::zerumProgram(program) => {
       let mut program = program;
-      if program.get_buffer().save_path!=String::new() {
-        fs::write(program.get_buffer().save_path.clone(), program.get_buffer().compile_text()).unwrap();
-        program.get_buffer().old_lines = program.get_buffer().lines.clone();
-        program.io = String::from("Saved!");
-        Proventus{value: Fructa::ProgramModifier(program), id: -5}
-      } else {
-        program.io = String::from("filename not provided!");
-        Proventus{value: Fructa::ProgramModifier(program), id: -5}
-      }
+      let current = program.current;
+      save_buffer(&mut program, current, false);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
     },
     FunctionArgs::singleProgram(filename, program) => {
       let mut program = program;
-      fs::write(combine_list_to_string(filename), program.get_buffer().compile_text()).unwrap();
-      program.get_buffer().old_lines = program.get_buffer().lines.clone();
-      program.io = String::from("Saved!");
+      let backup = program.config.ops.backup.clone();
+      let current = program.current;
+      let buffer = program.get_buffer();
+      let filename = combine_list_to_string(filename);
+      let canonical = std::fs::canonicalize(&filename).ok();
+      if filename == buffer.save_path || (canonical.is_some() && canonical == std::fs::canonicalize(&buffer.save_path).ok()) {
+        save_buffer(&mut program, current, false); // its own file, checked like `w`
+        return Proventus{value: Fructa::ProgramModifier(program), id: -5};
+      }
+      if buffer.large.is_some() {
+        program.io = format!("{} is a large file, only a window of it is loaded", buffer.buf_name);
+        return Proventus{value: Fructa::ProgramModifier(program), id: -5};
+      }
+      match buffer.contents().and_then(|x| crate::files::save_bytes(&filename, &x, &backup)) {
+        Ok(_) => {
+          let buffer = program.get_buffer();
+          buffer.old_lines = buffer.lines.clone();
+          buffer.changes = None;
+          buffer.saved_format = buffer.format.clone();
+          program.io = String::from("Saved!");
+        },
+        Err(e) => program.io = e,
+      }
       Proventus{value: Fructa::ProgramModifier(program), id: -5}
     }
     _ => panic!("how?")
   }
 }
 
-/*pub fn write_quit(arguments: Arguments) -> Proventus {
-  No idea how I'm gonna do that
-}*/
+/* wq - write current buffer then quit; x - same, but only writes if modified */
+pub fn write_quit(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      let current = program.current;
+      if save_buffer(&mut program, current, false) {
+        try_quit(&mut program);
+      }
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn exit_write(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      let current = program.current;
+      if !program.buffers[current].modified() || save_buffer(&mut program, current, false) {
+        try_quit(&mut program);
+      }
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn write_quit_all(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      let mut failed = vec![];
+      for i in 0..program.buffers.len() {
+        if program.buffers[i].modified() && !save_buffer(&mut program, i, false) {
+          failed.push(program.io.clone());
+        }
+      }
+      if !failed.is_empty() {
+        program.io = failed.join("; ");
+      } else {
+        try_quit(&mut program);
+      }
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
 
 pub fn move_buffer(arguments: Arguments) -> Proventus {
   match arguments.function {
@@ -454,6 +591,316 @@ pub fn set_buffer(arguments: Arguments) -> Proventus {
 }
 
 
+/* close [index] [force] - refuses to drop unsaved changes unless forced */
+pub fn close_force(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => close(Arguments{function: FunctionArgs::singleProgram(boolean(true), program)}),
+    FunctionArgs::singleProgram(index, program) => close(Arguments{function: FunctionArgs::doubleProgram(index, boolean(true), program)}),
+    _ => panic!("?")
+  }
+}
+
+pub fn close(arguments: Arguments) -> Proventus {
+  let (args, program) = match arguments.function {
+    FunctionArgs::zerumProgram(program) => (vec![], program),
+    FunctionArgs::singleProgram(a, program) => (vec![a], program),
+    FunctionArgs::doubleProgram(a, b, program) => (vec![a, b], program),
+    _ => panic!("?")
+  };
+  let mut program = program;
+  let mut index = program.current;
+  let mut force = false;
+  for arg in args {
+    match arg.value {
+      Fructa::Numerum(i) => index = i as usize,
+      Fructa::Condicio(b) => force = b,
+      _ => {}
+    }
+  }
+  if index >= program.buffers.len() {
+    program.io = format!("no buffer {}", index);
+  } else if !force && program.buffers[index].modified() {
+    program.io = format!("{} has unsaved changes (`bd!` to close anyway)", program.buffers[index].buf_name);
+  } else {
+    program.close(index);
+  }
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+/* session_save [file], session_load [file] - default file is the working directory's session */
+pub fn session_save(arguments: Arguments) -> Proventus {
+  let (path, program) = match arguments.function {
+    FunctionArgs::zerumProgram(program) => (crate::session::session_path(), program),
+    FunctionArgs::singleProgram(path, program) => (combine_list_to_string(path), program),
+    _ => panic!("?")
+  };
+  let mut program = program;
+  program.io = match crate::session::save(&program, &path) {
+    Ok(_) => format!("Session saved to {}", path),
+    Err(e) => e,
+  };
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn session_load(arguments: Arguments) -> Proventus {
+  let (path, program) = match arguments.function {
+    FunctionArgs::zerumProgram(program) => (crate::session::session_path(), program),
+    FunctionArgs::singleProgram(path, program) => (combine_list_to_string(path), program),
+    _ => panic!("?")
+  };
+  let mut program = program;
+  if let Err(e) = crate::session::load(&mut program, &path) {
+    program.io = e;
+  }
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn buffers(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      program.io = program.buffers.iter().enumerate().map(|(i, x)| format!("{}{}: {}{}",
+        if i == program.current {">"} else {""},
+        i,
+        if x.save_path != String::new() {&x.save_path} else {&x.buf_name},
+        if x.modified() {" [+]"} else {""}
+      )).collect::<Vec<String>>().join("  ");
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+fn string_argument(arguments: Arguments) -> (String, Program) {
+  match arguments.function {
+    FunctionArgs::singleProgram(name, program) => (combine_list_to_string(name), program),
+    _ => panic!("?")
+  }
+}
+
+/* file management in a directory buffer: touch/mkdir "name", rename/copy "destination" (on the selected entry),
+   remove asks before deleting the selected entry (remove! "path" doesn't) */
+pub fn touch(arguments: Arguments) -> Proventus {
+  let (name, mut program) = string_argument(arguments);
+  crate::explorer::create(&mut program, &name, false);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn mkdir(arguments: Arguments) -> Proventus {
+  let (name, mut program) = string_argument(arguments);
+  crate::explorer::create(&mut program, &name, true);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn rename(arguments: Arguments) -> Proventus {
+  let (name, mut program) = string_argument(arguments);
+  crate::explorer::move_selected(&mut program, &name, false);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn copy(arguments: Arguments) -> Proventus {
+  let (name, mut program) = string_argument(arguments);
+  crate::explorer::move_selected(&mut program, &name, true);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn remove(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::explorer::delete_selected(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn remove_force(arguments: Arguments) -> Proventus {
+  let (path, program) = match arguments.function {
+    FunctionArgs::zerumProgram(program) => (None, program),
+    FunctionArgs::singleProgram(path, program) => (Some(combine_list_to_string(path)), program),
+    _ => panic!("?")
+  };
+  let mut program = program;
+  program.confirm = None;
+  let path = path.or_else(|| match &program.get_buffer().buf_type {
+    BufferType::Directory(d) => d.subdirs.get(d.selected_index).filter(|x| x.name != "..").map(|x| x.abs_path.clone()),
+    _ => None,
+  });
+  match path {
+    Some(path) => crate::explorer::delete(&mut program, std::path::Path::new(&path)),
+    None => program.io = String::from("nothing selected"),
+  }
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+/* tree [root] - show/hide the tree explorer sidebar (a root starts a new tree), tree_focus - move the keys to/from it */
+pub fn tree(arguments: Arguments) -> Proventus {
+  let (root, program) = match arguments.function {
+    FunctionArgs::zerumProgram(program) => (None, program),
+    FunctionArgs::singleProgram(root, program) => (Some(combine_list_to_string(root)), program),
+    _ => panic!("?")
+  };
+  let mut program = program;
+  crate::tree::toggle(&mut program, root);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn tree_focus(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::tree::toggle_focus(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+/* find - fuzzy finder over the files of the working directory */
+pub fn find(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::finder::start(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+/* grep "text" [regex] - search the files of the working directory, results open in a buffer */
+pub fn grep(arguments: Arguments) -> Proventus {
+  let (pattern, regex, program) = match arguments.function {
+    FunctionArgs::zerumProgram(program) => (String::new(), false, program),
+    FunctionArgs::singleProgram(pattern, program) => (combine_list_to_string(pattern), false, program),
+    FunctionArgs::doubleProgram(pattern, regex, program) => (combine_list_to_string(pattern), regex.value == Fructa::Condicio(true), program),
+    _ => panic!("?")
+  };
+  let mut program = program;
+  crate::grep::search(&mut program, pattern, regex);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+/* set_line_ending "lf"/"crlf"/"cr", set_encoding "utf-8"/"utf-8-bom"/"utf-16le"/"utf-16be"/"latin-1" - format of the next write */
+pub fn set_line_ending(arguments: Arguments) -> Proventus {
+  let (name, mut program) = string_argument(arguments);
+  match crate::encoding::LineEnding::parse(&name) {
+    Some(line_ending) => {
+      program.io = format!("line endings: {}", line_ending.name());
+      program.get_buffer().format.line_ending = line_ending;
+    },
+    None => program.io = format!("unknown line ending {} (lf, crlf, cr)", name),
+  }
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+pub fn set_encoding(arguments: Arguments) -> Proventus {
+  let (name, mut program) = string_argument(arguments);
+  match crate::encoding::Encoding::parse(&name) {
+    Some(encoding) => {
+      let buffer = program.get_buffer();
+      let format = crate::encoding::Format {encoding, ..buffer.format.clone()};
+      match crate::encoding::encode(&buffer.lines, &format) {
+        Ok(_) => {
+          program.io = format!("encoding: {}", format.encoding.name());
+          program.get_buffer().format = format;
+        },
+        Err(e) => program.io = e,
+      }
+    },
+    None => program.io = format!("unknown encoding {} (utf-8, utf-8-bom, utf-16le, utf-16be, latin-1)", name),
+  }
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
+/* next_change, prev_change - move to the next/previous hunk changed since the last save; revert_hunk - undo the one under the cursor */
+pub fn next_change(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::changes::jump(&mut program, true);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn prev_change(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::changes::jump(&mut program, false);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn revert_hunk(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::changes::revert(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+/* blame - toggle who last changed the cursor's line; stage_hunk, reset_hunk - put the hunk under the cursor into the git index / back to HEAD */
+pub fn blame(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::git::toggle_blame(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn stage_hunk(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::git::stage_hunk(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+pub fn reset_hunk(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::git::reset_hunk(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+/* hex_edit - switch the current file between text and the hex editor */
+pub fn hex(arguments: Arguments) -> Proventus {
+  match arguments.function {
+    FunctionArgs::zerumProgram(program) => {
+      let mut program = program;
+      crate::hex::toggle(&mut program);
+      Proventus{value: Fructa::ProgramModifier(program), id: -5}
+    }
+    _ => panic!("?")
+  }
+}
+
+/* hex_find "de ad be ef" or hex_find "text" - jump to the next occurrence in a hex buffer */
+pub fn hex_find(arguments: Arguments) -> Proventus {
+  let (pattern, mut program) = string_argument(arguments);
+  crate::hex::find(&mut program, pattern);
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
 fn getw(config: Proventus, string: &str) -> Proventus {
   get(Arguments{function: FunctionArgs::double(config, Proventus{value: Fructa::Filum(String::from(string)), id: -1})})
 }
@@ -465,6 +912,30 @@ fn uwInt(int: Proventus) -> i32 {
   }
 }
 
+fn rgb(color: Proventus) -> Option<RGB> {
+  match color.value {
+    Fructa::Inventarii(i) => Some(RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8}),
+    _ => None
+  }
+}
+
+fn status_segments(list: Proventus) -> Vec<crate::StatusSegment> {
+  let mut segments = vec![];
+  if let Fructa::Inventarii(l) = list.value {
+    for segment in l {
+      if let Fructa::Causor(_) = segment.value {
+        segments.push(crate::StatusSegment {
+          text: combine_list_to_string(getw(segment.clone(), "text")),
+          foreground: rgb(getw(segment.clone(), "foreground")),
+          background: rgb(getw(segment.clone(), "background")),
+          interval: std::cmp::max(uwInt(getw(segment, "interval")), 0) as u32,
+        });
+      }
+    }
+  }
+  segments
+}
+
 pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
   match arguments.function {
     FunctionArgs::singleProgram(config, program) => {
@@ -480,6 +951,25 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
       let mut foklang = crate::FokLangSettings {..Default::default()};
 
 
+      let opsc = getw(config.clone(), "ops");
+      let mut leader = vec![KeyEvent{code: crate::KeyCode::Char(' '), modifiers: vec![]}];
+      match opsc.value {
+        Fructa::Causor(_) => {
+          let leaderc = getw(opsc.clone(), "leader");
+          if let Fructa::Inventarii(_) = leaderc.value {
+            match crate::input::parse_keys(&combine_list_to_string(leaderc.clone()), &[]) {
+              Some(l) => {
+                leader = l;
+              },
+              None => {
+                program.io = "Error: invalid ops.leader".to_string();
+              }
+            }
+          }
+        },
+        _ => {}
+      }
+
       let keybindsc = getw(config.clone(), "keybinds");
 
       match keybindsc.value {
@@ -488,39 +978,11 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
             match keybind.value {
               Fructa::Causor(_) => { //i.0 keyname  -  i.1 action
                 
-                let mut event = KeyEvent{code: crate::KeyCode::Escape, modifiers: vec![]};
                 let key = getw(keybind.clone(), "key");
-                match key.clone().value {
-                  Fructa::Inventarii(i) => {
-                    
-                    for key in combine_list_to_string(key.clone()).split("_") {
-                      match key {
-                        "ctrl" => {
-                          event.modifiers.push(crate::Modifier::Control);
-                        }, 
-                        "shift" => {
-                          event.modifiers.push(crate::Modifier::Shift);
-                        },
-                        "up" => {
-                          event.code = crate::KeyCode::Arrow(crate::Direction::Up);
-                        },
-                        "down" => {
-                          event.code = crate::KeyCode::Arrow(crate::Direction::Down);
-                        },
-                        "right" => {
-                         event.code = crate::KeyCode::Arrow(crate::Direction::Right);
-                        },
-                        "left" => {
-                          event.code = crate::KeyCode::Arrow(crate::Direction::Left);
-                        },
-                        _ => {
-                          event.code = crate::KeyCode::Char(key.chars().collect::<Vec<char>>()[0]);
-                        }
-                      }
-                    };
-                  }
-                  _ => {}
-                }
+                let keys = match key.clone().value {
+                  Fructa::Inventarii(_) => crate::input::parse_keys(&combine_list_to_string(key.clone()), &leader),
+                  _ => None,
+                };
                 let bol_fizyczny = match getw(keybind.clone(), "override").value {
                     Fructa::Condicio(b) => b,
                     _ => true,
@@ -551,7 +1013,19 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
                   _ => {}
                 }
 
-                keybinds.keybinds.push((event, combine_list_to_string(getw(keybind, "action")), bol_fizyczny, states));
+                let when = match getw(keybind.clone(), "when").value {
+                  Fructa::Inventarii(_) => combine_list_to_string(getw(keybind.clone(), "when")),
+                  _ => String::new(),
+                };
+
+                match keys {
+                  Some(keys) => {
+                    keybinds.add(keys, combine_list_to_string(getw(keybind, "action")), bol_fizyczny, states, when);
+                  },
+                  None => {
+                    program.io = format!("Error: invalid keybind key `{}`", combine_list_to_string(key));
+                  }
+                }
               },
               _ => {}
             }
@@ -560,10 +1034,47 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
         _ => {}
       }
 
-      let opsc = getw(config.clone(), "ops");
-      
       match opsc.value {
         Fructa::Causor(_) => {
+          if let Fructa::Condicio(b) = getw(opsc.clone(), "kitty_keyboard").value {
+            ops.kitty_keyboard = b;
+          }
+          if let Fructa::Numerum(i) = getw(opsc.clone(), "keybind_timeout").value {
+            if i<0 {
+              program.io = "Error: ops.keybind_timeout < 0".to_string();
+            } else {
+              ops.keybind_timeout = i as u32;
+            }
+          }
+          if let Fructa::Condicio(b) = getw(opsc.clone(), "auto_session").value {
+            ops.auto_session = b;
+          }
+          if let Fructa::Condicio(b) = getw(opsc.clone(), "git").value {
+            ops.git = b;
+          }
+          if let Fructa::Numerum(i) = getw(opsc.clone(), "swap_interval").value {
+            if i<0 {
+              program.io = "Error: ops.swap_interval < 0".to_string();
+            } else {
+              ops.swap_interval = i as u32;
+            }
+          }
+          if let Fructa::Numerum(i) = getw(opsc.clone(), "large_file").value {
+            if i<0 {
+              program.io = "Error: ops.large_file < 0".to_string();
+            } else {
+              ops.large_file = i as u32;
+            }
+          }
+          let backup = getw(opsc.clone(), "backup");
+          if let Fructa::Inventarii(_) = backup.value {
+            match &combine_list_to_string(backup) as &str {
+              "none" => ops.backup = crate::files::Backup::None,
+              "tilde" | "~" => ops.backup = crate::files::Backup::Tilde,
+              "timestamp" => ops.backup = crate::files::Backup::Timestamp,
+              _ => program.io = "Error: ops.backup must be \"none\", \"tilde\" or \"timestamp\"".to_string(),
+            }
+          }
           let tab_size = getw(opsc.clone(), "tab_size");
           match tab_size.value {
             Fructa::Numerum(i) => {
@@ -575,6 +1086,29 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
             },
             _ => {}
           }
+          let explorerc = getw(opsc.clone(), "explorer");
+          if let Fructa::Causor(_) = explorerc.value {
+            if let Fructa::Condicio(b) = getw(explorerc.clone(), "dirs_first").value {
+              ops.explorer.dirs_first = b;
+            }
+            if let Fructa::Condicio(b) = getw(explorerc.clone(), "show_hidden").value {
+              ops.explorer.show_hidden = b;
+            }
+            if let Fructa::Condicio(b) = getw(explorerc.clone(), "keep_open").value {
+              ops.explorer.keep_open = b;
+            }
+            let trash = getw(explorerc.clone(), "trash");
+            if let Fructa::Inventarii(_) = trash.value {
+              ops.explorer.trash = combine_list_to_string(trash);
+            }
+            match getw(explorerc.clone(), "tree_width").value {
+              Fructa::Numerum(i) if i > 1 => ops.explorer.tree_width = i as usize,
+              _ => {}
+            }
+            if let Fructa::Condicio(b) = getw(explorerc, "follow").value {
+              ops.explorer.follow = b;
+            }
+          }
           let line_numbers = getw(opsc.clone(), "line_numbers");
           match line_numbers.value {
             Fructa::Causor(_) => {
@@ -584,6 +1118,12 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
                 }
                 _ => {}
               }
+              if let Fructa::Condicio(b) = getw(line_numbers.clone(), "signs").value {
+                ops.line_numbers.signs = b;
+              }
+              if let Fructa::Condicio(b) = getw(line_numbers.clone(), "git_signs").value {
+                ops.line_numbers.git_signs = b;
+              }
               match getw(line_numbers.clone(), "background").value {
                 Fructa::Inventarii(i) => {
                   ops.line_numbers.background = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
@@ -653,7 +1193,7 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
         },
         _ => {}
       }
-      let colorsc = getw(config, "theme");
+      let colorsc = getw(config.clone(), "theme");
       match colorsc.value {
         Fructa::Causor(_) => {
           //println!("{:#?}", getw(colors.clone(), "background").value);
@@ -686,11 +1226,20 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
                 },
                 _ => {}
               }
-              match getw(buffer, "inactive").value {
-                Fructa::Inventarii(i) => {
-                  colors.inactive_buffer = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
-                },
-                _ => {}
+              if let Fructa::Inventarii(i) = getw(buffer.clone(), "inactive").value {
+                colors.inactive_buffer = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
+              }
+              if let Some(color) = rgb(getw(buffer.clone(), "foreground")) {
+                colors.buffer_foreground = color;
+              }
+              if let Some(color) = rgb(getw(buffer.clone(), "overflow")) {
+                colors.buffer_overflow = color;
+              }
+              if let Fructa::Numerum(_) = getw(buffer.clone(), "width").value {
+                colors.buffer_width = std::cmp::max(uwInt(getw(buffer.clone(), "width")), 4) as usize;
+              }
+              if let Fructa::Condicio(b) = getw(buffer, "index").value {
+                colors.buffer_index = b;
               }
             }
             _ => {}
@@ -715,6 +1264,12 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
             _ => {}
           }
 
+          let explorerc = getw(colorsc.clone(), "explorer");
+          if let Fructa::Causor(_) = explorerc.value {
+            if let Some(color) = rgb(getw(explorerc, "directory")) {
+              colors.directory = color;
+            }
+          }
           let line_nums = getw(colorsc.clone(), "line_numbers");
           match line_nums.value {
             Fructa::Causor(_) => {
@@ -724,6 +1279,15 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
                 },
                 _ => {}
               }
+              if let Fructa::Inventarii(i) = getw(line_nums.clone(), "added").value {
+                ops.line_numbers.added = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
+              }
+              if let Fructa::Inventarii(i) = getw(line_nums.clone(), "modified").value {
+                ops.line_numbers.modified = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
+              }
+              if let Fructa::Inventarii(i) = getw(line_nums.clone(), "deleted").value {
+                ops.line_numbers.deleted = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
+              }
               match getw(line_nums, "foreground").value {
                 Fructa::Inventarii(i) => {
                   ops.line_numbers.foreground = RGB{r: uwInt(i[0].clone()) as u8, g: uwInt(i[1].clone()) as u8, b: uwInt(i[2].clone()) as u8};
@@ -771,7 +1335,14 @@ pub fn load_fokedit_config(arguments: Arguments) -> Proventus {
       }
 
 
-      program.config = FokEditConfig{colors, elements, keybinds, ops, foklang};
+      let mut statusline = crate::StatusLine {..Default::default()};
+      let statuslinec = getw(config.clone(), "statusline");
+      if let Fructa::Causor(_) = statuslinec.value {
+        statusline.left = status_segments(getw(statuslinec.clone(), "left"));
+        statusline.right = status_segments(getw(statuslinec, "right"));
+      }
+
+      program.config = FokEditConfig{colors, elements, keybinds, ops, foklang, statusline};
       Proventus{value: Fructa::ProgramModifier(program), id: -5}
     }
     _ => panic!("?")
@@ -801,6 +1372,17 @@ pub fn open(arguments: Arguments) -> Proventus {
     _ => panic!("?")
   }
 }
+/* view "path" - open read-only */
+pub fn view(arguments: Arguments) -> Proventus {
+  let (fname, mut program) = string_argument(arguments);
+  let count = program.buffers.len();
+  program.open(fname);
+  if program.buffers.len() > count {
+    program.buffers.last_mut().unwrap().read_only = true;
+  }
+  Proventus{value: Fructa::ProgramModifier(program), id: -5}
+}
+
 pub fn reload(arguments: Arguments) -> Proventus {
   match arguments.function {
     FunctionArgs::zerumProgram(program) => {
@@ -818,22 +1400,82 @@ fn identifier(id: &str) -> Node {
 fn int(int: i32) -> Proventus {
   Proventus{value: Fructa::Numerum(int), id: -5}
 }
+fn string(string: &str) -> Proventus {
+  Proventus{value: Fructa::Inventarii(string.chars().map(|c| Proventus{value: Fructa::Ustulo(c), id: -5}).collect()), id: -5}
+}
+fn boolean(b: bool) -> Proventus {
+  Proventus{value: Fructa::Condicio(b), id: -5}
+}
+fn position(pos: (u32, u32)) -> Proventus {
+  Proventus{value: Fructa::Causor(vec![
+    (identifier("x"), int(pos.0 as i32)),
+    (identifier("y"), int(pos.1 as i32)),
+  ]), id: -5}
+}
 
 pub fn program(arguments: Arguments) -> Proventus {
   match arguments.function {
     FunctionArgs::zerumProgram(program) => {
       let mut program = program;
-      Proventus{value: Fructa::Causor(vec![
-        (identifier("cursor"), Proventus{value: Fructa::Causor(vec![
-          (identifier("x"), int(program.get_buffer().cursor.0 as i32)),
-          (identifier("y"), int(program.get_buffer().cursor.1 as i32)),
-        ]), id: -5})
-      ]), id: -5}
+      program_record(&mut program)
     }
     _ => panic!("?")
   }
 }
 
+/* the record `program` evaluates to; completion reads it too */
+pub fn program_record(program: &mut Program) -> Proventus {
+  let state = match program.state {
+    State::Control => "control",
+    State::Command => "command",
+    State::Input => "input",
+    State::Selection => "select",
+  };
+  let selecting = program.state == State::Selection;
+  let current = program.current;
+  let buffer_count = program.buffers.len();
+  let buffer = program.get_buffer();
+  let (sel_start, sel_end) = buffer.ordered_selection();
+  // large files only hold a window of their lines
+  let (offset, total) = match &buffer.large {
+    Some(l) => (l.start, l.total_lines()),
+    None => (0, buffer.lines.len()),
+  };
+  Proventus{value: Fructa::Causor(vec![
+    (identifier("cursor"), Proventus{value: Fructa::Causor(vec![
+      (identifier("x"), int(buffer.cursor.0 as i32)),
+      (identifier("y"), int((offset + buffer.cursor.1 as usize) as i32)),
+      (identifier("percent"), int(((offset + buffer.cursor.1 as usize + 1) * 100 / std::cmp::max(total, 1)) as i32)),
+    ]), id: -5}),
+    (identifier("state"), string(state)),
+    (identifier("buffer"), Proventus{value: Fructa::Causor(vec![
+      (identifier("type"), string(match buffer.buf_type {
+        crate::BufferType::File => "file",
+        crate::BufferType::Terminal => "terminal",
+        crate::BufferType::Directory(_) => "directory",
+        crate::BufferType::Hex(_) => "hex",
+      })),
+      (identifier("name"), string(&buffer.buf_name)),
+      (identifier("path"), string(&buffer.save_path)),
+      (identifier("filetype"), string(&buffer.filetype())),
+      (identifier("line_ending"), string(buffer.format.line_ending.name())),
+      (identifier("encoding"), string(buffer.format.encoding.name())),
+      (identifier("saved"), boolean(buffer.saved)),
+      (identifier("read_only"), boolean(buffer.read_only)),
+      (identifier("branch"), string(buffer.git.as_ref().map(|x| x.branch.as_str()).unwrap_or(""))),
+      (identifier("lines"), int(total as i32)),
+      (identifier("index"), int(current as i32)),
+    ]), id: -5}),
+    (identifier("selection"), Proventus{value: Fructa::Causor(vec![
+      (identifier("start"), position(sel_start)),
+      (identifier("end"), position(sel_end)),
+      (identifier("empty"), boolean(!selecting || sel_start == sel_end)),
+      (identifier("size"), int(if selecting {buffer.selection_size() as i32} else {0})),
+    ]), id: -5}),
+    (identifier("buffers"), int(buffer_count as i32)),
+  ]), id: -5}
+}
+
 pub fn set_fokedit_value(arguments: Arguments) -> Proventus {
   match arguments.function {
     FunctionArgs::doubleProgram(key, value, program) => {
@@ -998,6 +1640,25 @@ pub fn globals(_arguments: Arguments) -> Proventus {
 
 
 
+/* builtins that also have a forcing `name!` variant, the tokenizer keeps the `!` with these names only */
+pub const FORCED: [&str; 8] = ["quit", "q", "qa", "write", "w", "close", "bd", "remove"];
+
+/* short command names that would shadow common identifiers as bindings, so they are only
+   expanded as the first word of a command line, ex. `:rm!` runs `remove!` */
+pub const ALIASES: [(&str, &str); 8] = [
+  ("x", "exit_write"), ("ls", "buffers"), ("mv", "rename"), ("cp", "copy"),
+  ("rm", "remove"), ("rm!", "remove!"), ("ff", "find"), ("hex", "hex_edit"),
+];
+
+pub fn expand_alias(line: &str) -> String {
+  let start = line.len() - line.trim_start().len();
+  let end = line[start..].find(char::is_whitespace).map(|x| start + x).unwrap_or(line.len());
+  match ALIASES.iter().find(|(alias, _)| *alias == &line[start..end]) {
+    Some((_, name)) => line[..start].to_string() + name + &line[end..],
+    None => line.to_string(),
+  }
+}
+
 pub fn declare_builtins(env: &mut Environment) {
   let functions = vec![
     (String::from("get"), get as fn(Arguments) -> Proventus), (String::from("print"), print), (String::from("println"), println),
@@ -1009,11 +1670,22 @@ pub fn declare_builtins(env: &mut Environment) {
     (String::from("select"), select),
 
     ///PROGRAM
-    (String::from("quit"), quit), (String::from("q"), quit), (String::from("exit"), quit),
-    (String::from("write"), write), (String::from("w"), write), 
+    (String::from("quit"), quit), (String::from("q"), quit), (String::from("exit"), quit), (String::from("qa"), quit),
+    (String::from("quit!"), quit_force), (String::from("q!"), quit_force), (String::from("qa!"), quit_force),
+    (String::from("write"), write), (String::from("w"), write), (String::from("write!"), write_force), (String::from("w!"), write_force),
+    (String::from("wq"), write_quit), (String::from("exit_write"), exit_write), (String::from("wqa"), write_quit_all), (String::from("xa"), write_quit_all),
     (String::from("movebuf"), move_buffer), (String::from("mb"), move_buffer),
     (String::from("setbuf"), set_buffer), (String::from("b"), set_buffer),
-    (String::from("open"), open), (String::from("o"), open),
+    (String::from("close"), close), (String::from("bd"), close), (String::from("close!"), close_force), (String::from("bd!"), close_force),
+    (String::from("buffers"), buffers),
+    (String::from("session_save"), session_save), (String::from("session_load"), session_load),
+    (String::from("touch"), touch), (String::from("mkdir"), mkdir), (String::from("rename"), rename), (String::from("copy"), copy),
+    (String::from("remove"), remove), (String::from("remove!"), remove_force),
+    (String::from("tree"), tree), (String::from("tree_focus"), tree_focus), (String::from("find"), find), (String::from("grep"), grep),
+    (String::from("set_line_ending"), set_line_ending), (String::from("set_encoding"), set_encoding),
+    (String::from("hex_edit"), hex), (String::from("hex_find"), hex_find),
+    (String::from("next_change"), next_change), (String::from("prev_change"), prev_change), (String::from("revert_hunk"), revert_hunk), (String::from("blame"), blame), (String::from("stage_hunk"), stage_hunk), (String::from("reset_hunk"), reset_hunk),
+    (String::from("open"), open), (String::from("o"), open), (String::from("view"), view),
     (String::from("load_fokedit"), load_fokedit_config),
     (String::from("program"), program),
     (String::from("set"), set_fokedit_value),
@@ -302,10 +302,15 @@ impl Interpreter {
             }
           } else {
             if args.len()>0 {
-              if args[0].value.display_type() == self.evaluate(*i.clone(), env, program.clone()).value.display_type() { // TODO:    make the comparision better
-                args.push(self.evaluate(*i.clone(), env, program.clone()));
+              let value = self.evaluate(*i.clone(), env, program.clone());
+              let same_type = match (&args[0].value, &value.value) {
+                (AST::Fructa::Causor(_), AST::Fructa::Causor(_)) => true, // configs may differ in optional fields
+                _ => args[0].value.display_type() == value.value.display_type(), // TODO:    make the comparision better
+              };
+              if same_type {
+                args.push(value);
               } else {
-                panic!("List of type `{}` supplied with `{}`", args[0].value.display_type(), self.evaluate(*i.clone(), env, program.clone()).value.display_type());
+                panic!("List of type `{}` supplied with `{}`", args[0].value.display_type(), value.value.display_type());
               }
             } else {
               args.push(self.evaluate(*i.clone(), env, program.clone()));
@@ -519,8 +524,11 @@ impl Interpreter {
             AST::Fructa::Inventarii(li) => {
               match self.evaluate(*node_right, env, program).value {
                 AST::Fructa::Inventarii(li2) => {
+                  if li.len() != li2.len() {
+                    return AST::Proventus{value: AST::Fructa::Condicio(false), id: -1}
+                  }
                   for i in 0..li.len() {
-                    if li2[i as usize]!=li[i as usize] {
+                    if li2[i].value!=li[i].value {
                       return AST::Proventus{value: AST::Fructa::Condicio(false), id: -1}
                     }
                   }
@@ -1016,15 +1024,23 @@ impl Interpreter {
           expected = 1;
         }
 
-        else if f == builtins::quit || f == builtins::select || f == builtins::reload || f == builtins::program {
+        else if f == builtins::quit || f == builtins::quit_force || f == builtins::write_force || f == builtins::write_quit || f == builtins::exit_write || f == builtins::write_quit_all || f == builtins::select || f == builtins::reload || f == builtins::program || f == builtins::buffers || f == builtins::remove || f == builtins::tree_focus || f == builtins::find || f == builtins::hex || f == builtins::next_change || f == builtins::prev_change || f == builtins::revert_hunk || f == builtins::blame || f == builtins::stage_hunk || f == builtins::reset_hunk {
           fargs = builtins::FunctionArgs::zerumProgram(program.clone());
-        } else if f == builtins::write {
+        } else if f == builtins::write || f == builtins::session_save || f == builtins::session_load || f == builtins::remove_force || f == builtins::tree {
           if args_vec.len()>=1 {
             fargs = builtins::FunctionArgs::singleProgram(self.evaluate(args_vec[0].clone(), env, program.clone()), program.clone());
           } else {
             fargs = builtins::FunctionArgs::zerumProgram(program.clone());
           }
-        } else if f == builtins::load_fokedit_config || f == builtins::move_buffer || f == builtins::set_buffer || f == builtins::open {
+        } else if f == builtins::close || f == builtins::close_force || f == builtins::grep {
+          if args_vec.len() >= 2 {
+            fargs = builtins::FunctionArgs::doubleProgram(self.evaluate(args_vec[0].clone(), env, program.clone()), self.evaluate(args_vec[1].clone(), env, program.clone()), program.clone());
+          } else if args_vec.len() == 1 {
+            fargs = builtins::FunctionArgs::singleProgram(self.evaluate(args_vec[0].clone(), env, program.clone()), program.clone());
+          } else {
+            fargs = builtins::FunctionArgs::zerumProgram(program.clone());
+          }
+        } else if f == builtins::load_fokedit_config || f == builtins::move_buffer || f == builtins::set_buffer || f == builtins::open || f == builtins::touch || f == builtins::mkdir || f == builtins::rename || f == builtins::copy || f == builtins::set_line_ending || f == builtins::set_encoding || f == builtins::hex_find || f == builtins::view {
           fargs = builtins::FunctionArgs::singleProgram(self.evaluate(args_vec[0].clone(), env, program.clone()), program.clone());
         } else if f == builtins::set_fokedit_value {
           if args_vec.len() >= 2 {
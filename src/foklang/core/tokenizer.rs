@@ -101,7 +101,7 @@ impl Tokenizer {
   }
 
   pub fn tokenize(self, input: String) -> Vec<Token> {
-    let mut list_input: Vec<char> = input.replace("\\n", "\n").replace("\\t", "\t").replace("\\\"", "\"").replace("\\'", "\'").replace("\\x1b", "\x1b").chars().collect();
+    let mut list_input: Vec<char> = input.replace("\\n", "\n").replace("\\t", "\t").replace("\\'", "\'").replace("\\x1b", "\x1b").chars().collect();
     let mut tokens: Vec<Token> = [].to_vec();
     let mut pass;
     let speciales: HashMap<String, (TokenType, TokenValue)> = HashMap::from([(String::from("true"), (TokenType::Bool, TokenValue::Bool(true))), (String::from("false"), (TokenType::Bool, TokenValue::Bool(false))), (String::from("if"), (TokenType::If, TokenValue::Nullus)), (String::from("match"), (TokenType::Match, TokenValue::Nullus))]);
@@ -205,6 +205,9 @@ impl Tokenizer {
           let mut deval = String::new();
           list_input.remove(0);
           while list_input[0]!='"' {
+            if list_input[0]=='\\' && list_input.len()>1 && list_input[1]=='"' { // \" inside a string
+              list_input.remove(0);
+            }
             deval+=&list_input[0].to_string();
             list_input.remove(0);
           }
@@ -284,6 +287,10 @@ impl Tokenizer {
               tmp_ident+=&list_input[0].to_string();
               list_input.remove(0);
             }
+            if !list_input.is_empty() && list_input[0]=='!' && crate::foklang::core::builtins::FORCED.contains(&tmp_ident.as_str()) { // forcing variants, ex. `q!`
+              tmp_ident+="!";
+              list_input.remove(0);
+            }
             if speciales.contains_key(&tmp_ident) {
               tokens.push(Token{tokentype: speciales.get(&tmp_ident).unwrap().0.clone(), tokenvalue: speciales.get(&tmp_ident).unwrap().1.clone()});
             }
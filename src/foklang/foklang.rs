@@ -47,6 +47,13 @@ impl Foklang {
   pub fn raw_run(&mut self, input: String, program: Program) -> core::AST::Proventus {
     self.interpreter.evaluate(self.parser.parse(self.tokenizer.tokenize(input)), &mut self.env, program.clone())
   }
+  /* a nested scope where `name` is `value`, shadowing a builtin of the same name */
+  pub fn bind(&mut self, name: &str, value: core::AST::Proventus) {
+    let error_handler = self.env.error_handler;
+    let parent = std::mem::take(&mut self.env);
+    self.env = core::env::Environment{parent: Some(Box::new(parent)), error_handler, ..Default::default()};
+    self.env.declare(core::AST::Node{kind: core::AST::NodeKind::Identifier{symbol: String::from(name), childs: vec![]}}, value);
+  }
 }
 
 pub fn run(input: String, program: crate::Program) -> String {
@@ -0,0 +1,214 @@
+/* git - buffers whose file is inside a repository (asked of the `git` binary) get a second gutter column of signs
+   against HEAD next to the one against the last save, `blame` shows who last changed the cursor's line after it, `stage_hunk` puts
+   the hunk under the cursor into the index, `reset_hunk` takes it back to HEAD, and the branch is
+   `program.buffer.branch` */
+use std::{collections::hash_map::DefaultHasher, fs, hash::{Hash, Hasher}, io::Write, process::{Command, Stdio}, time::SystemTime};
+use crate::{BufferType, Editor, EditorBuffer, Program, RGB, changes, encoding};
+
+pub const BLAME_DELAY: i32 = 300; // ms without keys before the cursor's line gets blamed
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct GitFile {
+  pub root: String,
+  pub path: String,        // relative to root
+  pub branch: String,
+  pub head: Vec<String>,   // the file at HEAD, empty when it isn't committed
+  index: String,           // the index file, rewritten by commits, checkouts and staging
+  index_mtime: Option<SystemTime>,
+  changes: Option<changes::Changes>, // hunks against `head` for the gutter
+}
+
+/* stdout of a successful `git` run in `dir` */
+fn git(dir: &str, args: &[&str], input: Option<&[u8]>) -> Option<Vec<u8>> {
+  let mut child = Command::new("git").arg("-C").arg(dir).args(args)
+    .stdin(if input.is_some() {Stdio::piped()} else {Stdio::null()}).stdout(Stdio::piped()).stderr(Stdio::null())
+    .spawn().ok()?;
+  if let Some(input) = input {
+    let _ = child.stdin.take()?.write_all(input);
+  }
+  let output = child.wait_with_output().ok()?;
+  if output.status.success() {Some(output.stdout)} else {None}
+}
+
+fn text(bytes: Vec<u8>) -> String {
+  String::from_utf8_lossy(&bytes).trim_end().to_string()
+}
+
+fn refresh(file: &mut GitFile) {
+  file.index_mtime = fs::metadata(&file.index).and_then(|x| x.modified()).ok();
+  file.branch = git(&file.root, &["branch", "--show-current"], None).map(text).filter(|x| !x.is_empty())
+    .or_else(|| git(&file.root, &["rev-parse", "--short", "HEAD"], None).map(text)) // detached
+    .unwrap_or_default();
+  file.head = git(&file.root, &["show", &format!("HEAD:{}", file.path)], None).map(|x| encoding::decode(&x).0).unwrap_or_default();
+  file.changes = None; // HEAD may have moved
+}
+
+/* the buffer's hunks against HEAD, for the gutter */
+pub fn head_hunks(buffer: &mut EditorBuffer) -> &[changes::Hunk] {
+  if !changes::tracked(buffer) {
+    return &[];
+  }
+  match buffer.git.as_mut() {
+    Some(file) => changes::cached(&mut file.changes, &file.head, &buffer.lines),
+    None => &[],
+  }
+}
+
+/* the repository around `path`, None outside of one, for ignored files or without git */
+pub fn open(path: &str) -> Option<GitFile> {
+  let path = fs::canonicalize(path).ok()?;
+  let dir = path.parent()?.to_string_lossy().to_string();
+  let out = text(git(&dir, &["rev-parse", "--show-toplevel", "--absolute-git-dir"], None)?);
+  let (root, git_dir) = out.split_once('\n')?;
+  let relative = path.strip_prefix(root).ok()?.to_string_lossy().to_string();
+  if git(root, &["check-ignore", "-q", "--", &relative], None).is_some() {
+    return None;
+  }
+  let mut file = GitFile {root: root.to_string(), path: relative, branch: String::new(), head: vec![], index: format!("{}/index", git_dir), index_mtime: None, changes: None};
+  refresh(&mut file);
+  Some(file)
+}
+
+/* re-read HEAD and the branch of buffers whose repository's index changed (commit, checkout, stage...) */
+pub fn poll(program: &mut Program) -> bool {
+  let mut changed = false;
+  for buffer in program.buffers.iter_mut() {
+    if let Some(file) = buffer.git.as_mut() {
+      if fs::metadata(&file.index).and_then(|x| x.modified()).ok() != file.index_mtime {
+        refresh(file);
+        changed = true;
+      }
+    }
+  }
+  changed
+}
+
+fn current(program: &mut Program) -> Option<GitFile> {
+  let file = program.get_buffer().git.clone();
+  if file.is_none() {
+    program.io = String::from("not in a git repository");
+  }
+  file
+}
+
+/* the index's version of the file with the hunk under the cursor applied, hashed into the object store */
+pub fn stage_hunk(program: &mut Program) {
+  let mut file = match current(program) {
+    Some(f) => f,
+    None => return,
+  };
+  let buffer = program.get_buffer();
+  let index = git(&file.root, &["show", &format!(":{}", file.path)], None).map(|x| encoding::decode(&x).0).unwrap_or_default();
+  let hunks = changes::hunks(&index, &buffer.lines);
+  let hunk = match changes::under_cursor(&hunks, buffer.cursor.1 as usize) {
+    Some(h) => h.clone(),
+    None => {
+      program.io = String::from("no unstaged change under the cursor");
+      return;
+    }
+  };
+  let mut staged = index;
+  staged.splice(hunk.old.0..hunk.old.0 + hunk.old.1, buffer.lines[hunk.new.0..hunk.new.0 + hunk.new.1].to_vec());
+  let bytes = match encoding::encode(&staged, &buffer.format) {
+    Ok(b) => b,
+    Err(e) => {
+      program.io = e;
+      return;
+    }
+  };
+  let mode = git(&file.root, &["ls-files", "-s", "--", &file.path], None).map(text)
+    .and_then(|x| x.split(' ').next().map(|x| x.to_string())).filter(|x| !x.is_empty())
+    .unwrap_or(String::from("100644"));
+  let staged = git(&file.root, &["hash-object", "-w", "--stdin"], Some(&bytes)).map(text)
+    .and_then(|sha| git(&file.root, &["update-index", "--add", "--cacheinfo", &format!("{},{},{}", mode, sha, file.path)], None));
+  program.io = String::from(if staged.is_some() {"staged the hunk"} else {"git couldn't stage the hunk"});
+  refresh(&mut file);
+  let buffer = program.get_buffer();
+  buffer.git = Some(file);
+}
+
+/* the hunk under the cursor back to how HEAD has it */
+pub fn reset_hunk(program: &mut Program) {
+  if let Some(file) = current(program) {
+    changes::revert_to(program, &file.head);
+  }
+}
+
+/* `blame` - toggles the blame of the cursor's line */
+pub fn toggle_blame(program: &mut Program) {
+  program.blame = match program.blame {
+    Some(_) => None,
+    None => Some((0, String::new())),
+  };
+}
+
+fn blame_line(file: &GitFile, contents: &[u8], line: usize) -> String {
+  let out = match git(&file.root, &["blame", "--porcelain", "-L", &format!("{},{}", line, line), "--contents", "-", "--", &file.path], Some(contents)) {
+    Some(o) => text(o),
+    None => return String::from("no blame"),
+  };
+  let field = |name: &str| out.lines().find_map(|x| x.strip_prefix(name)).unwrap_or("").to_string();
+  if out.split(' ').next().map(|x| x.chars().all(|c| c == '0')).unwrap_or(false) {
+    return String::from("not committed yet");
+  }
+  let time = field("author-time ").parse::<i64>().map(crate::explorer::date).unwrap_or_default();
+  format!("{}, {} · {}", field("author "), time, field("summary "))
+}
+
+/* what the cursor line's blame depends on: the file, the line, its text and the line count (edits elsewhere
+   only shift it); None where there's nothing to blame */
+fn blame_key(program: &mut Program) -> Option<u64> {
+  if program.buffers.is_empty() {
+    return None;
+  }
+  let buffer = program.get_buffer();
+  if buffer.git.is_none() || !matches!(buffer.buf_type, BufferType::File) {
+    return None;
+  }
+  let mut hasher = DefaultHasher::new();
+  (&buffer.save_path, buffer.cursor.1, buffer.lines.len(), buffer.lines.get(buffer.cursor.1 as usize)).hash(&mut hasher);
+  Some(hasher.finish())
+}
+
+/* after a key - drop a blame that isn't about the cursor's line anymore, `update_blame` takes a new one once keys stop */
+pub fn invalidate_blame(program: &mut Program) {
+  if let Some((key, _)) = program.blame {
+    if Some(key) != blame_key(program) {
+      program.blame = Some((0, String::new()));
+    }
+  }
+}
+
+pub fn blame_pending(program: &mut Program) -> bool {
+  match program.blame {
+    Some((key, _)) => blame_key(program).map(|x| x != key).unwrap_or(false),
+    None => false,
+  }
+}
+
+/* blames the cursor's line, run after BLAME_DELAY without keys since `git blame` can take a while; true if it did */
+pub fn update_blame(program: &mut Program) -> bool {
+  if !blame_pending(program) {
+    return false;
+  }
+  let key = blame_key(program).unwrap();
+  let buffer = program.get_buffer();
+  let text = match (&buffer.git, encoding::encode(&buffer.lines, &buffer.format)) {
+    (Some(file), Ok(contents)) => blame_line(file, &contents, buffer.cursor.1 as usize + 1),
+    _ => String::new(),
+  };
+  program.blame = Some((key, text));
+  true
+}
+
+/* the blame written over the trailing blank space of a rendered row (which ends in "\n") */
+pub fn inline(row: String, blame: &str, color: RGB, foreground: RGB) -> String {
+  let body = row.strip_suffix('\n').unwrap_or(&row);
+  let blank = body.len() - body.trim_end_matches(' ').len();
+  if blank < 6 || blame.is_empty() {
+    return row;
+  }
+  let text = blame.chars().take(blank - 4).collect::<String>();
+  let rest = blank - 3 - text.chars().count();
+  format!("{}   \x1b[38;2;{color}m{}\x1b[38;2;{foreground}m{}\n", &body[..body.len() - blank], text, " ".repeat(rest))
+}
@@ -0,0 +1,152 @@
+/* project-wide search - `grep` lists every match under the working directory (see ignore.rs) in a results buffer,
+   one `path:line:col: text` per match; Enter on a result opens the file at the match */
+use std::fs;
+use crate::{BufferType, EditorBuffer, Editor, Program, regex::Regex};
+
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+const MAX_RESULTS: usize = 10000;
+const MATCH_STACK: usize = 16 * 1024 * 1024; // room for regex::MAX_DEPTH calls
+
+fn results_name(pattern: &str) -> String {
+  format!("grep: {}", pattern)
+}
+
+pub fn is_results(buffer: &EditorBuffer) -> bool {
+  buffer.save_path.is_empty() && buffer.buf_name.starts_with("grep: ")
+}
+
+/* returns how many lines the regex gave up on */
+fn search_file(path: &str, regex: &Option<Regex>, pattern: &str, results: &mut Vec<String>) -> usize {
+  match fs::metadata(path) {
+    Ok(m) if m.len() <= MAX_FILE_SIZE => {},
+    _ => return 0,
+  }
+  let bytes = match fs::read(path) {
+    Ok(b) => b,
+    Err(_) => return 0,
+  };
+  if bytes[..std::cmp::min(bytes.len(), 8192)].contains(&0) { // binary
+    return 0;
+  }
+  let mut skipped = 0;
+  for (n, line) in String::from_utf8_lossy(&bytes).split('\n').enumerate() {
+    if results.len() >= MAX_RESULTS {
+      break;
+    }
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let column = match regex {
+      Some(regex) => regex.find(&line.chars().collect::<Vec<char>>()).unwrap_or_else(|_| {
+        skipped += 1;
+        None
+      }).map(|x| x.0),
+      None => line.find(pattern).map(|x| line[..x].chars().count()),
+    };
+    if let Some(column) = column {
+      results.push(format!("{}:{}:{}: {}", path, n+1, column+1, line));
+    }
+  }
+  skipped
+}
+
+/* `grep "text"`, `grep "pattern" true` for a regex */
+pub fn search(program: &mut Program, pattern: String, is_regex: bool) {
+  if pattern.is_empty() {
+    program.io = String::from("grep: empty pattern");
+    return;
+  }
+  let regex = if is_regex {
+    match Regex::new(&pattern, false) {
+      Ok(r) => Some(r),
+      Err(e) => {
+        program.io = format!("grep: {}", e);
+        return;
+      }
+    }
+  } else {None};
+
+  // the regex recurses per matched char, more than the main thread's stack may take after the editor's own frames
+  let needle = pattern.clone();
+  let search = std::thread::Builder::new().stack_size(MATCH_STACK).spawn(move || {
+    let files = crate::ignore::walk(".");
+    let mut results = vec![];
+    let mut skipped = 0;
+    for file in &files {
+      skipped += search_file(file, &regex, &needle, &mut results);
+    }
+    (results, skipped)
+  });
+  let (results, skipped) = match search.map(|x| x.join()) {
+    Ok(Ok(r)) => r,
+    _ => {
+      program.io = String::from("grep: search failed");
+      return;
+    }
+  };
+  let skipped = if skipped > 0 {format!(", skipped {} lines the pattern backtracks too much on", skipped)} else {String::new()};
+  if results.is_empty() {
+    program.io = format!("grep: no matches for {}{}", pattern, skipped);
+    return;
+  }
+  let files = results.iter().map(|x| x.split(':').next().unwrap_or("")).collect::<std::collections::HashSet<&str>>().len();
+  program.io = format!("{}{} matches in {} files", if results.len() >= MAX_RESULTS {"first "} else {""}, results.len(), files);
+  program.io += &skipped;
+
+  // a new search replaces the results of the same pattern
+  let name = results_name(&pattern);
+  if let Some(index) = program.buffers.iter().position(|x| is_results(x) && x.buf_name == name) {
+    program.close(index);
+  }
+  program.buffers.push(EditorBuffer {
+    cursor: (0, 0),
+    selection: ((0,0), (0,0)),
+    old_lines: results.clone(),
+    lines: results,
+    buf_type: BufferType::File,
+    display_start_line: 0,
+    display_offset_collumn: 0,
+    buf_name: name,
+    save_path: String::new(),
+    saved: true,
+    format: Default::default(),
+    saved_format: Default::default(),
+    large: None,
+    disk: None,
+    read_only: false,
+    changes: None,
+    git: None,
+  });
+  program.current = program.buffers.len()-1;
+}
+
+/* `path:line:col: text` -> (path, line, col), the first `:number:number: ` decides so paths may contain `:` */
+fn parse(result: &str) -> Option<(String, usize, usize)> {
+  let mut start = 0;
+  while let Some(i) = result[start..].find(':') {
+    let colon = start + i;
+    let mut fields = result[colon+1..].splitn(3, ':');
+    if let (Some(line), Some(column), Some(_)) = (fields.next(), fields.next(), fields.next()) {
+      if let (Ok(line), Ok(column)) = (line.parse::<usize>(), column.parse::<usize>()) {
+        return Some((result[..colon].to_string(), line, column));
+      }
+    }
+    start = colon + 1;
+  }
+  None
+}
+
+/* Enter in a results buffer */
+pub fn jump(program: &mut Program) {
+  let buffer = program.get_buffer();
+  let line = buffer.lines[buffer.cursor.1 as usize].clone();
+  let (path, line, column) = match parse(&line) {
+    Some(r) => r,
+    None => return,
+  };
+  crate::explorer::open_file(program, path);
+  let buffer = program.get_buffer();
+  if let BufferType::File = buffer.buf_type {
+    let y = std::cmp::min(line.saturating_sub(1), buffer.lines.len().saturating_sub(1));
+    let x = std::cmp::min(column.saturating_sub(1), buffer.lines[y].chars().count());
+    buffer.cursor = (x as u32, y as u32);
+  }
+}
@@ -0,0 +1,218 @@
+/* hex editor - binary files open as `Hex` buffers, the bytes are the buffer's contents and `lines` is the
+   offset/hex/ASCII grid rendered from them. the cursor sits on a byte in the hex or the ASCII column,
+   typing in input state overwrites it (or appends past the end) */
+use std::fs;
+use crate::{BufferType, EditorBuffer, Editor, KeyCode, KeyEvent, Direction, Program, State, encoding};
+
+const ROW: usize = 16;             // bytes per row
+const HEX_COLUMN: usize = 10;      // `00000000  `
+const ASCII_COLUMN: usize = HEX_COLUMN + ROW * 3 + 1;
+
+/* anything with NUL bytes that doesn't look like UTF-16 */
+pub fn is_binary(bytes: &[u8], format: &encoding::Format) -> bool {
+  !matches!(format.encoding, encoding::Encoding::Utf16Le | encoding::Encoding::Utf16Be) && bytes[..std::cmp::min(bytes.len(), 8192)].contains(&0)
+}
+
+fn render_row(bytes: &[u8], row: usize) -> String {
+  let chunk = &bytes[row * ROW..std::cmp::min(bytes.len(), row * ROW + ROW)];
+  let hex = chunk.iter().map(|x| format!("{:02x} ", x)).collect::<String>();
+  let ascii = chunk.iter().map(|x| if x.is_ascii_graphic() || *x == b' ' {*x as char} else {'.'}).collect::<String>();
+  format!("{:08x}  {:<width$}|{:<ROW$}|", row * ROW, hex, ascii, width = ROW * 3)
+}
+
+fn rows(bytes: &[u8]) -> usize {
+  bytes.len() / ROW + 1
+}
+
+/* one row per 16 bytes, plus the row the next appended byte goes to when the last one is full */
+pub fn render(bytes: &[u8]) -> Vec<String> {
+  (0..rows(bytes)).map(|row| render_row(bytes, row)).collect()
+}
+
+fn bytes(buffer: &EditorBuffer) -> Option<&Vec<u8>> {
+  match &buffer.buf_type {
+    BufferType::Hex(b) => Some(b),
+    _ => None,
+  }
+}
+
+/* (byte under the cursor, in the ASCII column, on the low nibble) */
+fn position(buffer: &EditorBuffer) -> (usize, bool, bool) {
+  let (x, y) = (buffer.cursor.0 as usize, buffer.cursor.1 as usize);
+  let len = bytes(buffer).map(|x| x.len()).unwrap_or(0);
+  let (column, ascii, low) = if x >= ASCII_COLUMN {
+    (x - ASCII_COLUMN, true, false)
+  } else {
+    let x = x.saturating_sub(HEX_COLUMN);
+    (x / 3, false, x % 3 == 1)
+  };
+  let byte = std::cmp::min(y * ROW + std::cmp::min(column, ROW - 1), len);
+  (byte, ascii, low && byte < len)
+}
+
+fn place(buffer: &mut EditorBuffer, byte: usize, ascii: bool, low: bool) {
+  let column = byte % ROW;
+  let x = if ascii {ASCII_COLUMN + column} else {HEX_COLUMN + column * 3 + low as usize};
+  buffer.cursor = (x as u32, (byte / ROW) as u32);
+}
+
+/* keep the grid in step with the bytes and the cursor on a byte; generic editing of `lines` doesn't stick.
+   rendering the whole grid per key is too slow for big files, so only the row count and the rows around the
+   cursor (where edits happen) are checked */
+pub fn snap(program: &mut Program) {
+  if program.buffers.is_empty() {
+    return;
+  }
+  let buffer = program.get_buffer();
+  let stale = match bytes(buffer) {
+    Some(b) => {
+      let y = buffer.cursor.1 as usize;
+      buffer.lines.len() != rows(b) || (y.saturating_sub(1)..std::cmp::min(y + 2, rows(b))).any(|row| buffer.lines[row] != render_row(b, row))
+    },
+    None => return,
+  };
+  if stale {
+    buffer.lines = render(bytes(buffer).unwrap());
+  }
+  buffer.cursor.1 = std::cmp::min(buffer.cursor.1 as usize, buffer.lines.len() - 1) as u32;
+  let (byte, ascii, low) = position(buffer);
+  place(buffer, byte, ascii, low);
+}
+
+fn set(program: &mut Program, byte: usize, value: u8) {
+  let buffer = program.get_buffer();
+  if let BufferType::Hex(bytes) = &mut buffer.buf_type {
+    if byte < bytes.len() {
+      bytes[byte] = value;
+    } else {
+      bytes.push(value);
+    }
+    let row = byte / ROW;
+    buffer.lines[row] = render_row(bytes, row);
+    if buffer.lines.len() < rows(bytes) { // the last row filled up
+      buffer.lines.push(render_row(bytes, row + 1));
+    }
+  }
+}
+
+/* moving and typing in a hex buffer; false leaves the key to the usual handling */
+pub fn key(program: &mut Program, event: &KeyEvent) -> bool {
+  if program.buffers.is_empty() || !matches!(program.state, State::Control | State::Input) || !event.modifiers.is_empty() {
+    return false;
+  }
+  let input = program.state == State::Input;
+  let buffer = program.get_buffer();
+  let len = match bytes(buffer) {
+    Some(b) => b.len(),
+    None => return false,
+  };
+  let (byte, ascii, low) = position(buffer);
+  let last = if input {len} else {len.saturating_sub(1)}; // input state may append
+  match event.code.clone() {
+    KeyCode::Arrow(Direction::Left) => place(buffer, byte.saturating_sub(1), ascii, false),
+    KeyCode::Arrow(Direction::Right) => place(buffer, std::cmp::min(byte + 1, last), ascii, false),
+    KeyCode::Arrow(Direction::Up) => place(buffer, if byte >= ROW {byte - ROW} else {byte}, ascii, low),
+    KeyCode::Arrow(Direction::Down) => place(buffer, if byte + ROW <= last {byte + ROW} else {byte}, ascii, low),
+    KeyCode::Tab => place(buffer, byte, !ascii, false),
+    KeyCode::Backspace if input => place(buffer, byte.saturating_sub(1), ascii, false),
+    KeyCode::Char(c) if input && !ascii => {
+      let digit = match c.to_digit(16) {
+        Some(d) => d as u8,
+        None => return true,
+      };
+      let old = bytes(buffer).unwrap().get(byte).copied().unwrap_or(0);
+      let value = if low {(old & 0xf0) | digit} else {(digit << 4) | (old & 0x0f)};
+      set(program, byte, value);
+      let buffer = program.get_buffer();
+      if low {place(buffer, byte + 1, false, false)} else {place(buffer, byte, false, true)}
+    },
+    KeyCode::Char(_) | KeyCode::Colon if input => {
+      let c = if let KeyCode::Char(c) = event.code {c} else {':'};
+      if !c.is_ascii() {
+        program.io = String::from("only ASCII can be typed in the ASCII column");
+        return true;
+      }
+      set(program, byte, c as u8);
+      place(program.get_buffer(), byte + 1, true, false);
+    },
+    KeyCode::Escape => return false,
+    _ if input => {}, // nothing else edits the grid
+    _ => return false,
+  }
+  true
+}
+
+/* `hex_find "de ad be ef"` / `hex_find "text"` - next occurrence after the cursor, wrapping around */
+pub fn find(program: &mut Program, pattern: String) {
+  let digits = pattern.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>();
+  let needle = if !digits.is_empty() && digits.len().is_multiple_of(2) && digits.iter().all(|c| c.is_ascii_hexdigit()) {
+    digits.chunks(2).map(|x| u8::from_str_radix(&x.iter().collect::<String>(), 16).unwrap()).collect::<Vec<u8>>()
+  } else {
+    pattern.into_bytes()
+  };
+  let buffer = program.get_buffer();
+  let haystack = match bytes(buffer) {
+    Some(b) => b.clone(),
+    None => {
+      program.io = String::from("hex_find: not a hex buffer");
+      return;
+    }
+  };
+  if needle.is_empty() {
+    program.io = String::from("hex_find: empty pattern");
+    return;
+  }
+  let (byte, ascii, _) = position(buffer);
+  let matches = |i: &usize| haystack[*i..].starts_with(&needle);
+  let found = (byte + 1..haystack.len()).find(matches).or_else(|| (0..=std::cmp::min(byte, haystack.len().saturating_sub(1))).find(matches));
+  match found {
+    Some(i) => {
+      place(buffer, i, ascii, false);
+      program.io = format!("found at 0x{:x}", i);
+    },
+    None => program.io = String::from("hex_find: no match"),
+  }
+}
+
+/* `hex_edit` - switch the current buffer between text and hex, from what's on disk */
+pub fn toggle(program: &mut Program) {
+  let buffer = program.get_buffer();
+  if buffer.modified() {
+    program.io = String::from("hex: save the buffer first");
+    return;
+  }
+  match buffer.buf_type.clone() {
+    BufferType::File if buffer.large.is_none() => {
+      let bytes = match fs::read(&buffer.save_path) {
+        Ok(b) => b,
+        Err(_) => match encoding::encode(&buffer.lines, &buffer.format) {
+          Ok(b) => b,
+          Err(e) => {
+            program.io = e;
+            return;
+          }
+        }
+      };
+      buffer.lines = render(&bytes);
+      buffer.old_lines = buffer.lines.clone();
+      buffer.buf_type = BufferType::Hex(bytes);
+      buffer.cursor = (HEX_COLUMN as u32, 0);
+    },
+    BufferType::Hex(bytes) => {
+      let (lines, format) = encoding::decode(&bytes);
+      buffer.old_lines = lines.clone();
+      buffer.changes = None;
+      buffer.lines = lines;
+      buffer.saved_format = format.clone();
+      buffer.format = format;
+      buffer.buf_type = BufferType::File;
+      buffer.cursor = (0, 0);
+    },
+    _ => {
+      program.io = String::from("hex: only files can be shown as hex");
+      return;
+    }
+  }
+  buffer.display_start_line = 0;
+  buffer.display_offset_collumn = 0;
+}
@@ -0,0 +1,134 @@
+/* command history - kept in ~/.local/state/FokEdit/history (one command per line, oldest first), shared by every FokEdit */
+use std::{env, fs, path::Path};
+use crate::{KeyCode, KeyEvent, Modifier, Program};
+
+const HISTORY_SIZE: usize = 1000;
+
+pub fn history_path() -> String {
+  match env::var("XDG_STATE_HOME") {
+    Ok(dir) if dir != String::new() => dir + "/FokEdit/history",
+    _ => env::var("HOME").unwrap() + "/.local/state/FokEdit/history",
+  }
+}
+
+pub fn load() -> Vec<String> {
+  match fs::read_to_string(history_path()) {
+    Ok(contents) => contents.split("\n").filter(|x| !x.is_empty()).map(|x| x.to_string()).collect(),
+    Err(_) => vec![],
+  }
+}
+
+fn add(history: &mut Vec<String>, command: &str) {
+  history.retain(|x| x != command);
+  history.push(command.to_string());
+  if history.len() > HISTORY_SIZE {
+    history.drain(..history.len()-HISTORY_SIZE);
+  }
+}
+
+/* remember an evaluated command, here and in the history file (merged with what other instances wrote meanwhile) */
+pub fn push(program: &mut Program, command: String) {
+  program.io_history_index = 0;
+  if command.trim() == ":" || command.contains("\n") {
+    return;
+  }
+  add(&mut program.io_history, &command);
+
+  let mut stored = load();
+  add(&mut stored, &command);
+  let path = history_path();
+  if let Some(dir) = Path::new(&path).parent() {
+    let _ = fs::create_dir_all(dir);
+  }
+  let _ = crate::files::save(&path, &(stored.join("\n") + "\n"), &crate::files::Backup::None);
+}
+
+/* newest first, only commands starting with what was typed before browsing */
+fn matches(program: &Program) -> Vec<String> {
+  program.io_history.iter().rev().filter(|x| x.starts_with(&program.history_prefix) && **x != program.history_prefix).cloned().collect()
+}
+
+/* Up (older) / Down (newer) in Command state */
+pub fn browse(program: &mut Program, older: bool) {
+  if program.io_history_index == 0 {
+    program.history_prefix = program.io.clone();
+  }
+  let matches = matches(program);
+  if older && program.io_history_index < matches.len() {
+    program.io_history_index += 1;
+  } else if !older && program.io_history_index > 0 {
+    program.io_history_index -= 1;
+  }
+  program.io = if program.io_history_index == 0 {
+    program.history_prefix.clone()
+  } else {
+    matches[program.io_history_index-1].clone()
+  };
+  program.io_cursor = program.io.len() as u32;
+}
+
+/* ctrl-r: reverse incremental search, the IO line shows `(search) query: match` until accepted */
+pub fn start_search(program: &mut Program) {
+  program.history_search = Some((String::new(), 0, program.io.clone()));
+  show_search(program);
+}
+
+fn search_matches(program: &Program, query: &str) -> Vec<String> {
+  program.io_history.iter().rev().filter(|x| x.contains(query)).cloned().collect()
+}
+
+fn show_search(program: &mut Program) {
+  let (query, skip, _) = program.history_search.clone().unwrap();
+  let found = search_matches(program, &query).get(skip).cloned();
+  program.io = match found {
+    Some(command) => format!("(search) {}: {}", query, command),
+    None => format!("(failed search) {}:", query),
+  };
+  program.io_cursor = program.io.len() as u32;
+}
+
+pub fn search_key(program: &mut Program, event: KeyEvent) {
+  let (mut query, mut skip, original) = program.history_search.clone().unwrap();
+  let ctrl = event.modifiers == vec![Modifier::Control];
+  match event.code {
+    KeyCode::Char('r') if ctrl => { // next older match
+      if skip + 1 < search_matches(program, &query).len() {
+        skip += 1;
+      }
+    },
+    KeyCode::Char('g') | KeyCode::Char('c') if ctrl => {
+      cancel(program, original);
+      return;
+    },
+    KeyCode::Escape => {
+      cancel(program, original);
+      return;
+    },
+    KeyCode::Backspace => {
+      query.pop();
+      skip = 0;
+    },
+    KeyCode::Char(c) if event.modifiers.is_empty() => {
+      query.push(c);
+      skip = 0;
+    },
+    KeyCode::Colon => {
+      query.push(':');
+      skip = 0;
+    },
+    _ => { // anything else takes the match and leaves it for editing
+      program.io = search_matches(program, &query).get(skip).cloned().unwrap_or(original);
+      program.io_cursor = program.io.len() as u32;
+      program.history_search = None;
+      return;
+    }
+  }
+  program.history_search = Some((query, skip, original));
+  show_search(program);
+}
+
+fn cancel(program: &mut Program, original: String) {
+  program.io = original;
+  program.io_cursor = program.io.len() as u32;
+  program.history_search = None;
+}
@@ -0,0 +1,207 @@
+/* walking the project the way git sees it - .gitignore and .ignore files of every directory, .git itself skipped */
+use std::{fs, path::Path};
+
+const MAX_FILES: usize = 100000;
+
+#[derive(Debug,Clone)]
+struct Rule {
+  base: String,    // directory of the ignore file, relative to the walked root ("" for the root)
+  pattern: String,
+  negate: bool,    // `!pattern` re-includes
+  dir_only: bool,  // `pattern/`
+  anchored: bool,  // contains a `/`, matched from `base` instead of against the name at any depth
+}
+
+fn load(dir: &Path, base: &str, rules: &mut Vec<Rule>) {
+  for name in [".gitignore", ".ignore"] {
+    let contents = match fs::read_to_string(dir.join(name)) {
+      Ok(c) => c,
+      Err(_) => continue,
+    };
+    for line in contents.lines() {
+      let mut pattern = line.trim_end();
+      if pattern.is_empty() || pattern.starts_with("#") {
+        continue;
+      }
+      let negate = pattern.starts_with("!");
+      if negate {
+        pattern = &pattern[1..];
+      }
+      let dir_only = pattern.ends_with("/");
+      let pattern = pattern.trim_end_matches('/');
+      let anchored = pattern.contains("/");
+      rules.push(Rule {base: base.to_string(), pattern: pattern.trim_start_matches('/').to_string(), negate, dir_only, anchored});
+    }
+  }
+}
+
+/* `*` and `?` stay within a path component, `**` crosses them, `[abc]`/`[a-z]`/`[!a]` classes */
+pub fn glob(pattern: &[char], text: &[char]) -> bool {
+  match pattern.first() {
+    None => text.is_empty(),
+    Some('*') if pattern.get(1) == Some(&'*') => {
+      let rest = &pattern[2..];
+      let rest = if rest.first() == Some(&'/') {&rest[1..]} else {rest}; // `a/**/b` matches `a/b`
+      (0..=text.len()).any(|i| glob(rest, &text[i..]))
+    },
+    Some('*') => {
+      let mut i = 0;
+      loop {
+        if glob(&pattern[1..], &text[i..]) {
+          return true;
+        }
+        if i == text.len() || text[i] == '/' {
+          return false;
+        }
+        i += 1;
+      }
+    },
+    Some('?') => !text.is_empty() && text[0] != '/' && glob(&pattern[1..], &text[1..]),
+    Some('[') => {
+      let end = match pattern.iter().skip(2).position(|c| *c == ']') {
+        Some(e) => e + 2,
+        None => return text.first() == Some(&'[') && glob(&pattern[1..], &text[1..]),
+      };
+      let c = match text.first() {
+        Some(c) if *c != '/' => *c,
+        _ => return false,
+      };
+      let negate = pattern[1] == '!' || pattern[1] == '^';
+      let class = &pattern[if negate {2} else {1}..end];
+      let mut found = false;
+      let mut i = 0;
+      while i < class.len() {
+        if i + 2 < class.len() && class[i+1] == '-' {
+          found |= class[i] <= c && c <= class[i+2];
+          i += 3;
+        } else {
+          found |= class[i] == c;
+          i += 1;
+        }
+      }
+      found != negate && glob(&pattern[end+1..], &text[1..])
+    },
+    Some('\\') if pattern.len() > 1 => text.first() == Some(&pattern[1]) && glob(&pattern[2..], &text[1..]),
+    Some(p) => text.first() == Some(p) && glob(&pattern[1..], &text[1..]),
+  }
+}
+
+/* the last matching rule decides */
+fn ignored(rules: &[Rule], path: &str, is_dir: bool) -> bool {
+  let mut result = false;
+  for rule in rules {
+    if rule.dir_only && !is_dir {
+      continue;
+    }
+    let relative = if rule.base.is_empty() {path} else {
+      match path.strip_prefix(&(rule.base.clone() + "/")) {
+        Some(r) => r,
+        None => continue,
+      }
+    };
+    let text = if rule.anchored {relative} else {relative.rsplit('/').next().unwrap_or(relative)};
+    if glob(&rule.pattern.chars().collect::<Vec<char>>(), &text.chars().collect::<Vec<char>>()) {
+      result = !rule.negate;
+    }
+  }
+  result
+}
+
+fn walk_dir(root: &Path, relative: &str, rules: &mut Vec<Rule>, files: &mut Vec<String>) {
+  let dir = if relative.is_empty() {root.to_path_buf()} else {root.join(relative)};
+  let count = rules.len();
+  load(&dir, relative, rules);
+  let mut entries = match fs::read_dir(&dir) {
+    Ok(e) => e.filter_map(|x| x.ok()).collect::<Vec<fs::DirEntry>>(),
+    Err(_) => vec![],
+  };
+  entries.sort_by_key(|x| x.file_name());
+  for entry in entries {
+    if files.len() >= MAX_FILES {
+      break;
+    }
+    let name = entry.file_name().to_string_lossy().to_string();
+    if name == ".git" {
+      continue;
+    }
+    let path = if relative.is_empty() {name} else {format!("{}/{}", relative, name)};
+    let is_dir = entry.file_type().map(|x| x.is_dir()).unwrap_or(false); // symlinked directories aren't followed
+    if ignored(rules, &path, is_dir) {
+      continue;
+    }
+    if is_dir {
+      walk_dir(root, &path, rules, files);
+    } else {
+      files.push(path);
+    }
+  }
+  rules.truncate(count);
+}
+
+/* files under `root`, relative to it */
+pub fn walk(root: &str) -> Vec<String> {
+  let mut files = vec![];
+  walk_dir(Path::new(root), "", &mut vec![], &mut files);
+  files
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn matches(pattern: &str, text: &str) -> bool {
+    glob(&pattern.chars().collect::<Vec<char>>(), &text.chars().collect::<Vec<char>>())
+  }
+
+  fn rule(base: &str, line: &str) -> Rule {
+    let negate = line.starts_with('!');
+    let pattern = line.trim_start_matches('!');
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    Rule {base: base.to_string(), pattern: pattern.trim_start_matches('/').to_string(), negate, dir_only, anchored: pattern.contains('/')}
+  }
+
+  #[test]
+  fn wildcards() {
+    assert!(matches("*.rs", "main.rs"));
+    assert!(!matches("*.rs", "src/main.rs"));
+    assert!(matches("src/*.rs", "src/main.rs"));
+    assert!(matches("?.txt", "a.txt"));
+    assert!(!matches("?.txt", "ab.txt"));
+    assert!(!matches("a?b", "a/b"));
+  }
+
+  #[test]
+  fn double_star() {
+    assert!(matches("**/*.o", "a/b/c.o"));
+    assert!(matches("a/**/b", "a/b"));
+    assert!(matches("a/**/b", "a/x/y/b"));
+    assert!(matches("a/**", "a/x/y"));
+    assert!(!matches("a/**/b", "c/a/b"));
+  }
+
+  #[test]
+  fn classes() {
+    assert!(matches("[abc].txt", "b.txt"));
+    assert!(matches("file[0-9]", "file7"));
+    assert!(!matches("file[0-9]", "filex"));
+    assert!(matches("[!a]x", "bx"));
+    assert!(!matches("[!a]x", "ax"));
+    assert!(matches("[ab", "[ab")); // unclosed, literal
+    assert!(matches("\\*", "*"));
+    assert!(!matches("\\*", "a"));
+  }
+
+  #[test]
+  fn rules() {
+    let rules = vec![rule("", "*.log"), rule("", "!keep.log"), rule("", "build/"), rule("sub", "/local")];
+    assert!(ignored(&rules, "x.log", false));
+    assert!(ignored(&rules, "deep/dir/x.log", false));
+    assert!(!ignored(&rules, "deep/keep.log", false));
+    assert!(ignored(&rules, "build", true));
+    assert!(!ignored(&rules, "build", false));
+    assert!(ignored(&rules, "sub/local", false));
+    assert!(!ignored(&rules, "sub/x/local", false));
+    assert!(!ignored(&rules, "local", false));
+  }
+}
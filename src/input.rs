@@ -0,0 +1,402 @@
+/* terminal input decoder - turns raw stdin bytes into KeyEvents.
+   understands utf-8, control chars, alt/meta prefixes, legacy xterm CSI/SS3 sequences (with modifiers)
+   and the kitty keyboard protocol (CSI ... u) */
+use crate::{Direction, KeyCode, KeyEvent, Modifier};
+
+const ESCAPE: u8 = 27;
+const BACKSPACE: u8 = 127;
+const TAB: u8 = b'\t';
+const ENTER: u8 = b'\n';
+
+const ESCAPE_TIMEOUT: i32 = 25; // ms to wait for the rest of a sequence before treating ESC as a keypress
+
+/* what waiting on stdin gave: a byte, nothing within the timeout, or end of input (terminal gone) */
+pub enum Stdin {
+  Byte(u8),
+  Timeout,
+  Closed,
+}
+
+/* `timeout: None` blocks */
+pub fn wait_byte(timeout: Option<i32>) -> Stdin {
+  loop {
+    if let Some(ms) = timeout {
+      let mut fds = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+      let r = unsafe { libc::poll(&mut fds, 1, ms) };
+      if r < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+        continue;
+      }
+      if r == 0 {
+        return Stdin::Timeout;
+      }
+    }
+    let mut byte = 0u8;
+    let r = unsafe { libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+    if r == 1 {
+      return Stdin::Byte(byte);
+    } else if r < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+      continue; // SIGWINCH & co.
+    }
+    return Stdin::Closed; // EOF or a read error
+  }
+}
+
+/* None on timeout or EOF, all the decoder needs to know mid-sequence */
+pub fn read_byte(timeout: Option<i32>) -> Option<u8> {
+  match wait_byte(timeout) {
+    Stdin::Byte(byte) => Some(byte),
+    _ => None,
+  }
+}
+
+fn key(code: KeyCode, modifiers: Vec<Modifier>) -> Option<KeyEvent> {
+  Some(normalize(KeyEvent { code, modifiers }))
+}
+
+/* one canonical form per key: sorted modifiers, shift+letter is the uppercase letter */
+pub fn normalize(mut event: KeyEvent) -> KeyEvent {
+  event.modifiers.sort();
+  event.modifiers.dedup();
+  match event.code {
+    KeyCode::Char(c) if event.modifiers == vec![Modifier::Shift] && c.is_lowercase() => {
+      event.code = KeyCode::Char(c.to_uppercase().next().unwrap());
+      event.modifiers = vec![];
+    },
+    _ => {}
+  }
+  event
+}
+
+/* decode a single key, `byte` being the first byte already read */
+pub fn decode(byte: u8) -> Option<KeyEvent> {
+  decode_from(byte, &mut read_byte)
+}
+
+/* the rest of the key from `read` instead of stdin */
+fn decode_from(byte: u8, read: &mut dyn FnMut(Option<i32>) -> Option<u8>) -> Option<KeyEvent> {
+  match byte {
+    ESCAPE => {
+      match read(Some(ESCAPE_TIMEOUT)) {
+        None => key(KeyCode::Escape, vec![]),
+        Some(b'[') => decode_csi(read),
+        Some(b'O') => {
+          match read(Some(ESCAPE_TIMEOUT)) {
+            None => key(KeyCode::Char('O'), vec![Modifier::Alt]),
+            Some(b) => decode_ss3(b),
+          }
+        },
+        Some(b) => { // ESC-prefixed key => alt
+          let mut event = decode_from(b, read)?;
+          event.modifiers.push(Modifier::Alt);
+          key(event.code, event.modifiers)
+        }
+      }
+    },
+    ENTER | b'\r' => key(KeyCode::Enter, vec![]),
+    TAB => key(KeyCode::Tab, vec![]),
+    BACKSPACE | 8 => key(KeyCode::Backspace, vec![]),
+    b':' => key(KeyCode::Colon, vec![]),
+    0 => key(KeyCode::Char(' '), vec![Modifier::Control]),
+    1..=26 => key(KeyCode::Char((b'a' + byte - 1) as char), vec![Modifier::Control]),
+    28..=31 => key(KeyCode::Char((byte + 64) as char), vec![Modifier::Control]),
+    0x80.. => decode_utf8(byte, read),
+    _ => key(KeyCode::Char(byte as char), vec![]),
+  }
+}
+
+fn decode_utf8(lead: u8, read: &mut dyn FnMut(Option<i32>) -> Option<u8>) -> Option<KeyEvent> {
+  let len = match lead {
+    0xc0..=0xdf => 2,
+    0xe0..=0xef => 3,
+    0xf0..=0xf7 => 4,
+    _ => return None,
+  };
+  let mut bytes = vec![lead];
+  for _ in 1..len {
+    bytes.push(read(Some(ESCAPE_TIMEOUT))?);
+  }
+  let c = std::str::from_utf8(&bytes).ok()?.chars().next()?;
+  key(KeyCode::Char(c), vec![])
+}
+
+/* xterm sends `1 + mask` (shift=1 alt=2 ctrl=4 meta=8), kitty the same but 8 is super and 32 meta */
+fn decode_modifiers(param: u32, kitty: bool) -> Vec<Modifier> {
+  let mask = param.saturating_sub(1);
+  let mut modifiers = vec![];
+  if mask & 1 != 0 { modifiers.push(Modifier::Shift); }
+  if mask & 2 != 0 { modifiers.push(Modifier::Alt); }
+  if mask & 4 != 0 { modifiers.push(Modifier::Control); }
+  if mask & 8 != 0 { modifiers.push(if kitty {Modifier::Super} else {Modifier::Meta}); }
+  if mask & 32 != 0 { modifiers.push(Modifier::Meta); }
+  modifiers
+}
+
+fn decode_ss3(byte: u8) -> Option<KeyEvent> {
+  let code = match byte {
+    b'A' => KeyCode::Arrow(Direction::Up),
+    b'B' => KeyCode::Arrow(Direction::Down),
+    b'C' => KeyCode::Arrow(Direction::Right),
+    b'D' => KeyCode::Arrow(Direction::Left),
+    b'H' => KeyCode::Home,
+    b'F' => KeyCode::End,
+    b'M' => KeyCode::Enter,
+    b'P'..=b'S' => KeyCode::F(byte - b'P' + 1),
+    _ => return None,
+  };
+  key(code, vec![])
+}
+
+fn decode_csi(read: &mut dyn FnMut(Option<i32>) -> Option<u8>) -> Option<KeyEvent> {
+  let mut params = String::new();
+  let final_byte = loop {
+    let b = read(Some(ESCAPE_TIMEOUT))?;
+    if (0x40..=0x7e).contains(&b) {
+      break b;
+    }
+    params.push(b as char);
+  };
+  if params.starts_with(['<', '?', '>', '=']) {
+    return None; // mouse reports and terminal replies, not keys
+  }
+
+  // `number[:alt...];modifiers[:event];text`
+  let fields = params.split(";").map(|x| x.split(":").map(|y| y.parse::<u32>().unwrap_or(0)).collect::<Vec<u32>>()).collect::<Vec<Vec<u32>>>();
+  let number = fields[0][0];
+  let modifier_param = fields.get(1).map(|x| x[0]).unwrap_or(1);
+  let event_type = fields.get(1).and_then(|x| x.get(1)).copied().unwrap_or(1);
+  let modifiers = decode_modifiers(modifier_param, final_byte == b'u');
+
+  let code = match final_byte {
+    b'A' => KeyCode::Arrow(Direction::Up),
+    b'B' => KeyCode::Arrow(Direction::Down),
+    b'C' => KeyCode::Arrow(Direction::Right),
+    b'D' => KeyCode::Arrow(Direction::Left),
+    b'H' => KeyCode::Home,
+    b'F' => KeyCode::End,
+    b'P'..=b'S' => KeyCode::F(final_byte - b'P' + 1),
+    b'Z' => return key(KeyCode::Tab, vec![Modifier::Shift]),
+    b'~' => match number {
+      1 | 7 => KeyCode::Home,
+      2 => KeyCode::Insert,
+      3 => KeyCode::Delete,
+      4 | 8 => KeyCode::End,
+      5 => KeyCode::PageUp,
+      6 => KeyCode::PageDown,
+      11..=15 => KeyCode::F((number - 10) as u8),
+      17..=21 => KeyCode::F((number - 11) as u8),
+      23 | 24 => KeyCode::F((number - 12) as u8),
+      200 => return key(read_paste(read), vec![]),
+      _ => return None,
+    },
+    b'u' => {
+      if event_type == 3 {
+        return None; // key release
+      }
+      match number {
+        27 => KeyCode::Escape,
+        13 | 57414 => KeyCode::Enter,
+        9 => KeyCode::Tab,
+        127 | 8 => KeyCode::Backspace,
+        57399..=57408 => KeyCode::Char(char::from_digit(number - 57399, 10)?), // keypad digits
+        58 => KeyCode::Colon,
+        _ => KeyCode::Char(char::from_u32(number).filter(|c| !('\u{e000}'..='\u{f8ff}').contains(c))?), // skip other private-use functional keys
+      }
+    },
+    _ => return None,
+  };
+  key(code, modifiers)
+}
+
+/* bracketed paste: everything between ESC[200~ and ESC[201~ is a single pasted block */
+fn read_paste(read: &mut dyn FnMut(Option<i32>) -> Option<u8>) -> KeyCode {
+  let mut bytes: Vec<u8> = vec![];
+  while let Some(b) = read(None) {
+    bytes.push(b);
+    if bytes.ends_with(b"\x1b[201~") {
+      bytes.truncate(bytes.len()-6);
+      break;
+    }
+  }
+  KeyCode::Paste(String::from_utf8_lossy(&bytes).replace("\r\n", "\n").replace("\r", "\n"))
+}
+
+
+/* keybind spec grammar:
+     "ctrl+shift+p", "ctrl_left", "f5"  - a whole word naming one key, modifiers joined by `+` or `_`
+     "<leader>ff", "<C-x>gg"            - `<...>` holds a chord (or `leader`), any other character is a key of its own
+     "g g"                              - whitespace separates keys of a sequence */
+pub fn parse_keys(spec: &str, leader: &[KeyEvent]) -> Option<Vec<KeyEvent>> {
+  let mut keys = vec![];
+  for word in spec.split_whitespace() {
+    if let Some(chord) = parse_chord(word, &['+', '_']) {
+      keys.push(chord);
+      continue;
+    }
+    let chars = word.chars().collect::<Vec<char>>();
+    let mut i = 0;
+    while i < chars.len() {
+      if chars[i] == '<' {
+        if let Some(end) = chars[i..].iter().position(|x| *x == '>') {
+          let inner = chars[i+1..i+end].iter().collect::<String>();
+          if inner.to_lowercase() == "leader" {
+            keys.extend(leader.iter().cloned());
+          } else {
+            keys.push(parse_chord(&inner, &['+', '_', '-'])?);
+          }
+          i += end+1;
+          continue;
+        }
+      }
+      keys.push(parse_chord(&chars[i].to_string(), &[])?);
+      i += 1;
+    }
+  }
+  if keys.is_empty() {
+    return None;
+  }
+  Some(keys)
+}
+
+fn parse_chord(chord: &str, separators: &[char]) -> Option<KeyEvent> {
+  if chord.chars().count() == 1 {
+    let c = chord.chars().next().unwrap();
+    return key(if c == ':' {KeyCode::Colon} else {KeyCode::Char(c)}, vec![]);
+  }
+  let mut modifiers = vec![];
+  let mut code = None;
+  for part in chord.split(separators) {
+    match part {
+      "ctrl" | "control" | "C" => modifiers.push(Modifier::Control),
+      "shift" | "S" => modifiers.push(Modifier::Shift),
+      "alt" | "A" => modifiers.push(Modifier::Alt),
+      "meta" | "M" => modifiers.push(Modifier::Meta),
+      "super" | "D" => modifiers.push(Modifier::Super),
+      _ => {
+        if code.is_some() {
+          return None;
+        }
+        code = Some(key_name(part)?);
+      }
+    }
+  }
+  key(code?, modifiers)
+}
+
+fn key_name(name: &str) -> Option<KeyCode> {
+  if name.chars().count() == 1 {
+    let c = name.chars().next().unwrap();
+    return Some(if c == ':' {KeyCode::Colon} else {KeyCode::Char(c)});
+  }
+  Some(match &name.to_lowercase() as &str {
+    "up" => KeyCode::Arrow(Direction::Up),
+    "down" => KeyCode::Arrow(Direction::Down),
+    "left" => KeyCode::Arrow(Direction::Left),
+    "right" => KeyCode::Arrow(Direction::Right),
+    "home" => KeyCode::Home,
+    "end" => KeyCode::End,
+    "pageup" | "pgup" => KeyCode::PageUp,
+    "pagedown" | "pgdn" => KeyCode::PageDown,
+    "insert" | "ins" => KeyCode::Insert,
+    "delete" | "del" => KeyCode::Delete,
+    "backspace" | "bs" => KeyCode::Backspace,
+    "enter" | "return" | "cr" => KeyCode::Enter,
+    "tab" => KeyCode::Tab,
+    "escape" | "esc" => KeyCode::Escape,
+    "space" => KeyCode::Char(' '),
+    "colon" => KeyCode::Colon,
+    "lt" => KeyCode::Char('<'),
+    f if f.starts_with("f") && f[1..].parse::<u8>().is_ok_and(|x| (1..=12).contains(&x)) => KeyCode::F(f[1..].parse::<u8>().unwrap()),
+    _ => return None,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode_bytes(bytes: &[u8]) -> Option<KeyEvent> {
+    let mut rest = bytes[1..].iter().copied();
+    let event = decode_from(bytes[0], &mut |_| rest.next());
+    assert_eq!(rest.next(), None, "{:?} left bytes unread", bytes);
+    event
+  }
+
+  fn event(code: KeyCode, modifiers: Vec<Modifier>) -> Option<KeyEvent> {
+    Some(KeyEvent {code, modifiers})
+  }
+
+  #[test]
+  fn plain() {
+    assert_eq!(decode_bytes(b"a"), event(KeyCode::Char('a'), vec![]));
+    assert_eq!(decode_bytes(b"\r"), event(KeyCode::Enter, vec![]));
+    assert_eq!(decode_bytes(b"\x01"), event(KeyCode::Char('a'), vec![Modifier::Control]));
+    assert_eq!(decode_bytes(b"\x1b"), event(KeyCode::Escape, vec![]));
+    assert_eq!(decode_bytes(b"\x1bx"), event(KeyCode::Char('x'), vec![Modifier::Alt]));
+    assert_eq!(decode_bytes("ó".as_bytes()), event(KeyCode::Char('ó'), vec![]));
+  }
+
+  #[test]
+  fn csi() {
+    assert_eq!(decode_bytes(b"\x1b[A"), event(KeyCode::Arrow(Direction::Up), vec![]));
+    assert_eq!(decode_bytes(b"\x1b[1;5D"), event(KeyCode::Arrow(Direction::Left), vec![Modifier::Control]));
+    assert_eq!(decode_bytes(b"\x1b[1;4C"), event(KeyCode::Arrow(Direction::Right), vec![Modifier::Shift, Modifier::Alt]));
+    assert_eq!(decode_bytes(b"\x1b[3~"), event(KeyCode::Delete, vec![]));
+    assert_eq!(decode_bytes(b"\x1b[6;3~"), event(KeyCode::PageDown, vec![Modifier::Alt]));
+    assert_eq!(decode_bytes(b"\x1b[15~"), event(KeyCode::F(5), vec![]));
+    assert_eq!(decode_bytes(b"\x1b[Z"), event(KeyCode::Tab, vec![Modifier::Shift]));
+    assert_eq!(decode_bytes(b"\x1b[<0;3;4M"), None); // mouse report
+  }
+
+  #[test]
+  fn ss3() {
+    assert_eq!(decode_bytes(b"\x1bOB"), event(KeyCode::Arrow(Direction::Down), vec![]));
+    assert_eq!(decode_bytes(b"\x1bOQ"), event(KeyCode::F(2), vec![]));
+    assert_eq!(decode_bytes(b"\x1bOH"), event(KeyCode::Home, vec![]));
+    assert_eq!(decode_bytes(b"\x1bO"), event(KeyCode::Char('O'), vec![Modifier::Alt]));
+  }
+
+  #[test]
+  fn kitty() {
+    assert_eq!(decode_bytes(b"\x1b[97;5u"), event(KeyCode::Char('a'), vec![Modifier::Control]));
+    assert_eq!(decode_bytes(b"\x1b[97;2u"), event(KeyCode::Char('A'), vec![])); // shift+letter is the uppercase letter
+    assert_eq!(decode_bytes(b"\x1b[13u"), event(KeyCode::Enter, vec![]));
+    assert_eq!(decode_bytes(b"\x1b[27;1u"), event(KeyCode::Escape, vec![]));
+    assert_eq!(decode_bytes(b"\x1b[57401u"), event(KeyCode::Char('2'), vec![]));
+    assert_eq!(decode_bytes(b"\x1b[115;9u"), event(KeyCode::Char('s'), vec![Modifier::Super]));
+    assert_eq!(decode_bytes(b"\x1b[97;5:3u"), None); // release
+    assert_eq!(decode_bytes(b"\x1b[57441u"), None); // left shift
+  }
+
+  #[test]
+  fn paste() {
+    assert_eq!(decode_bytes(b"\x1b[200~a\r\nb\x1b[A\x1b[201~"), event(KeyCode::Paste(String::from("a\nb\x1b[A")), vec![]));
+    assert_eq!(decode_bytes(b"\x1b[200~\x1b[201~"), event(KeyCode::Paste(String::new()), vec![]));
+  }
+
+  #[test]
+  fn keys() {
+    let ctrl = |c| KeyEvent {code: KeyCode::Char(c), modifiers: vec![Modifier::Control]};
+    let plain = |c| KeyEvent {code: KeyCode::Char(c), modifiers: vec![]};
+    let leader = [plain(' ')];
+    assert_eq!(parse_keys("ctrl+s", &leader), Some(vec![ctrl('s')]));
+    assert_eq!(parse_keys("ctrl_shift_left", &leader), Some(vec![KeyEvent {code: KeyCode::Arrow(Direction::Left), modifiers: vec![Modifier::Control, Modifier::Shift]}]));
+    assert_eq!(parse_keys("gg", &leader), Some(vec![plain('g'), plain('g')]));
+    assert_eq!(parse_keys("g g", &leader), Some(vec![plain('g'), plain('g')]));
+    assert_eq!(parse_keys("<leader>ff", &leader), Some(vec![plain(' '), plain('f'), plain('f')]));
+    assert_eq!(parse_keys("<C-x>k", &leader), Some(vec![ctrl('x'), plain('k')]));
+    assert_eq!(parse_keys("f5 :", &leader), Some(vec![KeyEvent {code: KeyCode::F(5), modifiers: vec![]}, KeyEvent {code: KeyCode::Colon, modifiers: vec![]}]));
+    assert_eq!(parse_keys("<C-nope>", &leader), None);
+    assert_eq!(parse_keys("  ", &leader), None);
+  }
+
+  #[test]
+  fn chords() {
+    assert_eq!(parse_chord("shift+a", &['+']), Some(KeyEvent {code: KeyCode::Char('A'), modifiers: vec![]}));
+    assert_eq!(parse_chord("alt+ctrl+Del", &['+']), Some(KeyEvent {code: KeyCode::Delete, modifiers: vec![Modifier::Control, Modifier::Alt]}));
+    assert_eq!(parse_chord("D-space", &['-']), Some(KeyEvent {code: KeyCode::Char(' '), modifiers: vec![Modifier::Super]}));
+    assert_eq!(parse_chord("<", &[]), Some(KeyEvent {code: KeyCode::Char('<'), modifiers: vec![]}));
+    assert_eq!(parse_chord("ctrl+a+b", &['+']), None);
+    assert_eq!(parse_chord("f13", &['+']), None);
+    assert_eq!(parse_chord("ctrl+s", &[]), None); // a word, not a chord
+  }
+}
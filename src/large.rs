@@ -0,0 +1,163 @@
+/* large files - files over ops.large_file MB aren't read in at open. a background thread indexes where lines start,
+   the buffer's `lines` only hold a window of them around the cursor, read from disk when the cursor gets near its edges.
+   such buffers are read-only */
+use std::{fs, io::Read, os::unix::fs::FileExt, sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}};
+use crate::{EditorBuffer, Editor, Program};
+
+const WINDOW: usize = 4000;   // lines materialised at once
+const MARGIN: usize = 500;    // re-window when the cursor gets this close to an edge
+const CHUNK: usize = 1 << 20;
+
+#[derive(Debug,Clone)]
+pub struct LargeFile {
+  pub path: String,
+  pub size: u64,
+  pub start: usize,             // line number of lines[0]
+  index: Arc<Mutex<Vec<u64>>>,  // byte offset of every line start found so far
+  done: Arc<AtomicBool>,
+  indexed: (usize, bool),       // lines indexed and whether indexing went on, when the window was read
+}
+impl PartialEq for LargeFile {
+  fn eq(&self, other: &Self) -> bool {
+    self.path == other.path && self.start == other.start && Arc::ptr_eq(&self.index, &other.index)
+  }
+}
+
+impl LargeFile {
+  pub fn total_lines(&self) -> usize {
+    self.index.lock().unwrap().len()
+  }
+  pub fn indexing(&self) -> bool {
+    !self.done.load(Ordering::Relaxed)
+  }
+  fn progress(&self) -> (usize, bool) {
+    (self.total_lines(), self.indexing())
+  }
+  /* `count` lines from line `start`, fewer when the index doesn't reach that far yet */
+  fn read(&self, start: usize, count: usize) -> Vec<String> {
+    let (offsets, end) = {
+      let index = self.index.lock().unwrap();
+      if count == 0 || start >= index.len() {
+        return vec![];
+      }
+      let last = std::cmp::min(start + count, index.len());
+      if last == index.len() && self.indexing() { // the last known line may still be growing
+        let count = last - start - 1;
+        drop(index);
+        return self.read(start, count);
+      }
+      let end = if last < index.len() {index[last]} else {self.size};
+      (index[start..last].to_vec(), end)
+    };
+    let mut bytes = vec![0; (end - offsets[0]) as usize];
+    let file = match fs::File::open(&self.path) {
+      Ok(f) => f,
+      Err(_) => return vec![],
+    };
+    if file.read_exact_at(&mut bytes, offsets[0]).is_err() {
+      return vec![];
+    }
+    let mut lines = vec![];
+    for (i, offset) in offsets.iter().enumerate() {
+      let from = (offset - offsets[0]) as usize;
+      let to = offsets.get(i+1).map(|x| (x - offsets[0]) as usize).unwrap_or(bytes.len());
+      let line = &bytes[from..to];
+      let line = line.strip_suffix(b"\n").unwrap_or(line);
+      let line = line.strip_suffix(b"\r").unwrap_or(line);
+      lines.push(String::from_utf8_lossy(line).to_string());
+    }
+    lines
+  }
+}
+
+/* scan `file` from `offset`, adding line starts to `index` chunk by chunk; returns where it stopped */
+fn index_lines(file: &mut fs::File, mut offset: u64, index: &Arc<Mutex<Vec<u64>>>, until_lines: usize) -> Option<u64> {
+  let mut chunk = vec![0; CHUNK];
+  loop {
+    let read = match file.read(&mut chunk) {
+      Ok(0) | Err(_) => return None,
+      Ok(n) => n,
+    };
+    let starts = chunk[..read].iter().enumerate().filter(|(_, b)| **b == b'\n').map(|(i, _)| offset + i as u64 + 1).collect::<Vec<u64>>();
+    offset += read as u64;
+    let mut index = index.lock().unwrap();
+    index.extend(starts);
+    if index.len() >= until_lines {
+      return Some(offset);
+    }
+  }
+}
+
+pub fn is_large(path: &str, program: &Program) -> bool {
+  let limit = program.config.ops.large_file as u64 * 1024 * 1024;
+  limit > 0 && fs::metadata(path).map(|x| x.is_file() && x.len() > limit).unwrap_or(false)
+}
+
+/* indexes the first window right away, the rest in the background */
+pub fn open(path: &str) -> Result<(Vec<String>, LargeFile), String> {
+  let mut file = fs::File::open(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+  let size = file.metadata().map(|x| x.len()).unwrap_or(0);
+  let index = Arc::new(Mutex::new(vec![0]));
+  let done = Arc::new(AtomicBool::new(false));
+  match index_lines(&mut file, 0, &index, WINDOW + 1) {
+    Some(offset) => {
+      let (index, done) = (index.clone(), done.clone());
+      std::thread::spawn(move || {
+        index_lines(&mut file, offset, &index, usize::MAX);
+        done.store(true, Ordering::Relaxed);
+      });
+    },
+    None => done.store(true, Ordering::Relaxed),
+  }
+  let mut large = LargeFile {path: path.to_string(), size, start: 0, index, done, indexed: (0, true)};
+  large.indexed = large.progress();
+  let mut lines = large.read(0, WINDOW);
+  if lines.is_empty() {
+    lines.push(String::new());
+  }
+  Ok((lines, large))
+}
+
+/* re-window buffers whose cursor got near an edge the file goes on past, or whose window is short of WINDOW and
+   the index grew since it was read; anything else keeps the lines already loaded */
+pub fn update(program: &mut Program) {
+  if program.buffers.is_empty() {
+    return;
+  }
+  let buffer = program.get_buffer();
+  let mut large = match &buffer.large {
+    Some(l) => l.clone(),
+    None => return,
+  };
+  let progress = large.progress();
+  let cursor = large.start + buffer.cursor.1 as usize;
+  let start = std::cmp::min(cursor.saturating_sub(WINDOW / 2), progress.0.saturating_sub(WINDOW));
+  let near_edge = (buffer.cursor.1 as usize) < MARGIN || buffer.cursor.1 as usize + MARGIN >= buffer.lines.len();
+  let moved = near_edge && start != large.start;
+  let grown = buffer.lines.len() < WINDOW && progress != large.indexed;
+  if !moved && !grown {
+    return;
+  }
+  large.indexed = progress;
+  let lines = large.read(start, WINDOW);
+  if lines.is_empty() || (start == large.start && lines.len() == buffer.lines.len()) {
+    buffer.large = Some(large);
+    return;
+  }
+  let shift = start as i64 - large.start as i64;
+  large.start = start;
+  buffer.cursor.1 = (buffer.cursor.1 as i64 - shift) as u32;
+  buffer.display_start_line = std::cmp::max(buffer.display_start_line as i64 - shift, 0) as u32;
+  buffer.old_lines = lines.clone();
+  buffer.lines = lines;
+  buffer.large = Some(large);
+}
+
+pub fn indexing(program: &Program) -> bool {
+  program.buffers.iter().any(|x| x.large.as_ref().map(|l| l.indexing()).unwrap_or(false))
+}
+
+/* first line of the window, for line numbers and the status line */
+pub fn offset(buffer: &EditorBuffer) -> usize {
+  buffer.large.as_ref().map(|x| x.start).unwrap_or(0)
+}
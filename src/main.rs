@@ -1,7 +1,26 @@
 #![allow(unused_doc_comments)]
 mod foklang;
+mod input;
+mod files;
+mod diff;
+mod swap;
+mod session;
+mod history;
+mod completion;
+mod explorer;
+mod tree;
+mod ignore;
+mod finder;
+mod regex;
+mod grep;
+mod encoding;
+mod large;
+mod hex;
+mod watch;
+mod changes;
+mod git;
 use {libc, std::{
-  env, fs, io::{self, IsTerminal, Read, Write}, path::Path, sync::{Arc,Mutex}
+  collections::HashMap, env, fs, io::{self, IsTerminal, Read, Write}, path::{Path, PathBuf}, sync::{Arc,Mutex}
 }};
 
 
@@ -69,11 +88,13 @@ pub struct KeyEvent {
   pub code: KeyCode,
   pub modifiers: Vec<Modifier>,
 }
-#[derive(Debug,PartialEq,Clone)]
-pub enum Modifier {
+#[derive(Debug,PartialEq,Eq,PartialOrd,Ord,Clone)]
+pub enum Modifier { // order matters - modifiers are kept sorted so keybinds compare equal
   Control,
   Shift,
-  //why even do it at this point
+  Alt,
+  Meta,
+  Super,
 }
 #[derive(Debug,PartialEq,Clone)]
 pub enum Direction {
@@ -92,20 +113,14 @@ pub enum KeyCode {
   Backspace,
   Delete,
   Arrow(Direction),
+  Home,
+  End,
+  PageUp,
+  PageDown,
+  Insert,
+  F(u8),
   Char(char),
-}
-
-const ESCAPE: char = 27 as char;
-const BACKSPACE: char = '\u{7f}';
-const TAB: char = '\t';
-const ENTER: char = '\n';
-
-fn getch() -> char {
-  io::stdin().bytes().next().unwrap().unwrap() as char
-}
-fn get_arrow() -> KeyCode {
-  match getch() {'A' => KeyCode::Arrow(Direction::Up), 'B' => KeyCode::Arrow(Direction::Down), 'C' => KeyCode::Arrow(Direction::Right), 'D' => KeyCode::Arrow(Direction::Left),
-                                                           _ => KeyCode::Escape }
+  Paste(String),
 }
 
 #[derive(Debug,Clone,PartialEq)]
@@ -126,14 +141,69 @@ impl std::fmt::Display for RGB {
 }
 
 
+type Keybind = (Vec<KeyEvent>,String,bool,Vec<State>,String); // (key sequence, action, override, states, `when` predicate)
+
 #[derive(Debug,Clone,PartialEq)]
 pub struct Keybinds {
-  keybinds: Vec<(KeyEvent,String,bool,Vec<State>)>,
+  keybinds: Vec<Keybind>,
+  trie: KeyTrie,
 }
 
 impl Default for Keybinds {
   fn default() -> Self {
-    Self {keybinds: vec![]}
+    Self {keybinds: vec![], trie: KeyTrie{..Default::default()}}
+  }
+}
+impl Keybinds {
+  fn add(&mut self, keys: Vec<KeyEvent>, action: String, overrides: bool, states: Vec<State>, when: String) {
+    self.trie.insert(&keys, self.keybinds.len());
+    self.keybinds.push((keys, action, overrides, states, when));
+  }
+  /* keybinds ending exactly at `keys` for which `valid` holds */
+  fn matching(&self, keys: &[KeyEvent], valid: &dyn Fn(usize) -> bool) -> Vec<usize> {
+    match self.trie.walk(keys) {
+      Some(node) => node.binds.iter().copied().filter(|x| valid(*x)).collect(),
+      None => vec![],
+    }
+  }
+  /* whether some longer valid keybind starts with `keys` */
+  fn is_prefix(&self, keys: &[KeyEvent], valid: &dyn Fn(usize) -> bool) -> bool {
+    match self.trie.walk(keys) {
+      Some(node) => node.children.iter().any(|x| x.1.any_bind(valid)),
+      None => false,
+    }
+  }
+}
+
+/* prefix tree of key sequences, nodes hold indices into Keybinds.keybinds */
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct KeyTrie {
+  children: Vec<(KeyEvent, KeyTrie)>,
+  binds: Vec<usize>,
+}
+impl KeyTrie {
+  fn insert(&mut self, keys: &[KeyEvent], id: usize) {
+    if keys.is_empty() {
+      self.binds.push(id);
+      return;
+    }
+    match self.children.iter().position(|x| x.0 == keys[0]) {
+      Some(i) => self.children[i].1.insert(&keys[1..], id),
+      None => {
+        let mut child = KeyTrie{..Default::default()};
+        child.insert(&keys[1..], id);
+        self.children.push((keys[0].clone(), child));
+      }
+    }
+  }
+  fn walk(&self, keys: &[KeyEvent]) -> Option<&KeyTrie> {
+    if keys.is_empty() {
+      return Some(self);
+    }
+    self.children.iter().find(|x| x.0 == keys[0])?.1.walk(&keys[1..])
+  }
+  fn any_bind(&self, valid: &dyn Fn(usize) -> bool) -> bool {
+    self.binds.iter().any(|x| valid(*x)) || self.children.iter().any(|x| x.1.any_bind(valid))
   }
 }
 
@@ -170,6 +240,12 @@ pub struct ColorConfig {
 
   active_buffer: RGB,
   inactive_buffer: RGB,
+  buffer_foreground: RGB,
+  buffer_overflow: RGB,   // `<`/`>` shown when tabs don't fit
+  buffer_width: usize,    // columns per tab
+  buffer_index: bool,     // prefix tabs with their buffer index
+
+  directory: RGB,         // directories in the explorer
 
   io_background: RGB,
   io_foreground: RGB,
@@ -181,6 +257,8 @@ impl Default for ColorConfig {
           io_background: RGB{r: 56, g: 68, b: 37}, io_foreground: RGB{r: 255, g: 255, b: 255}*/ 
       Self {background: RGB{r: 20, g: 20, b: 20}, foreground: RGB{r: 255, g: 255, b: 255}, border: RGB{r: 40, g: 40, b: 40},
           active_buffer: RGB{r: 80, g: 80, b: 100}, inactive_buffer: RGB{r: 80, g: 80, b: 80},
+          buffer_foreground: RGB{r: 255, g: 255, b: 255}, buffer_overflow: RGB{r: 120, g: 120, b: 120}, buffer_width: 16, buffer_index: true,
+          directory: RGB{r: 100, g: 150, b: 255},
           empty_line_background: RGB{r: 20, g: 20, b: 20}, empty_line_foreground: RGB{r: 0, g: 0, b: 200},
           io_background: RGB{r: 10, g: 10, b: 10}, io_foreground: RGB{r: 250, g: 250, b: 250},
           selection_color: RGB{r: 255, g: 0, b: 0},
@@ -193,16 +271,39 @@ pub struct LineNumbers {
   enable: bool,
   background: RGB,
   foreground: RGB,
+  signs: bool,       // added/modified/deleted lines marked in the gutter
+  git_signs: bool,   // a second column of them against HEAD, for files in a git repository
+  added: RGB,
+  modified: RGB,
+  deleted: RGB,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct ExplorerOps {
+  dirs_first: bool,
+  show_hidden: bool,      // initially, `.` toggles it per explorer
+  keep_open: bool,        // opening a file keeps the explorer buffer
+  trash: String,          // `remove` moves entries here instead of deleting them, empty deletes
+  tree_width: usize,      // columns of the `tree` sidebar
+  follow: bool,           // the sidebar selects the file of the active buffer
 }
 
 #[derive(Debug,Clone,PartialEq)]
 pub struct FokEditOps {
   line_numbers: LineNumbers,
+  explorer: ExplorerOps,
   tab_size: usize,
+  kitty_keyboard: bool,   // ask the terminal for the kitty keyboard protocol (unambiguous ctrl/alt combos)
+  keybind_timeout: u32,   // ms to wait for the next key of a keybind sequence
+  backup: files::Backup,  // copy of the previous version kept on save
+  swap_interval: u32,     // ms between swap file updates, 0 disables swap files
+  auto_session: bool,     // restore the working directory's session when started without files, save it on quit
+  large_file: u32,        // MB above which files open lazily and read-only, 0 never does
+  git: bool,              // signs against HEAD, blame and hunk staging for files in a git repository
 }
 impl Default for FokEditOps {
   fn default() -> Self {
-    Self {tab_size: 4, line_numbers: LineNumbers{enable: false, background: RGB{r: 20, g: 20, b: 20}, foreground: RGB{r: 150, g: 150, b: 150}}}
+    Self {tab_size: 4, kitty_keyboard: false, keybind_timeout: 1000, backup: files::Backup::None, swap_interval: 4000, auto_session: false, large_file: 64, git: true, explorer: ExplorerOps{dirs_first: true, show_hidden: false, keep_open: false, trash: String::new(), tree_width: 30, follow: true}, line_numbers: LineNumbers{enable: false, background: RGB{r: 20, g: 20, b: 20}, foreground: RGB{r: 150, g: 150, b: 150}, signs: true, git_signs: true, added: RGB{r: 120, g: 180, b: 80}, modified: RGB{r: 200, g: 170, b: 60}, deleted: RGB{r: 210, g: 80, b: 80}}}
   }
 }
 
@@ -218,6 +319,21 @@ impl Default for FokLangSettings {
   }
 }
 
+/* status line - foklang expressions evaluated every frame */
+#[derive(Debug,Clone,PartialEq)]
+pub struct StatusSegment {
+  text: String,               // foklang expression, its displayed value is shown
+  foreground: Option<RGB>,    // defaults to io colors
+  background: Option<RGB>,
+  interval: u32,              // ms; >0 re-evaluates on a timer instead of on every program change (ex. `exec`)
+}
+
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct StatusLine {
+  left: Vec<StatusSegment>,
+  right: Vec<StatusSegment>,
+}
+
 #[derive(Debug,Clone,PartialEq)]
 pub struct FokEditConfig {
   colors: ColorConfig,
@@ -225,6 +341,7 @@ pub struct FokEditConfig {
   keybinds: Keybinds,
   ops: FokEditOps,
   foklang: FokLangSettings,
+  statusline: StatusLine,
   /*
   highlighting: HighlightingConfig,
   options: FokEditOpts,
@@ -233,7 +350,8 @@ pub struct FokEditConfig {
 impl Default for FokEditConfig {
   fn default() -> Self {
     Self {colors: ColorConfig{..Default::default()}, elements: ElementsConfig{..Default::default()},
-    keybinds: Keybinds{..Default::default()},  ops: FokEditOps{..Default::default()}, foklang: FokLangSettings{..Default::default()}}
+    keybinds: Keybinds{..Default::default()},  ops: FokEditOps{..Default::default()}, foklang: FokLangSettings{..Default::default()},
+    statusline: StatusLine{..Default::default()}}
   }
 }
 
@@ -244,6 +362,10 @@ struct Subdir {
   name: String,
   abs_path: String,
   selected: bool,
+  is_dir: bool,
+  size: u64,
+  modified: i64,  // mtime, unix seconds
+  mode: u32,      // permission bits
 }
 
 #[derive(Debug,Clone,PartialEq)]
@@ -251,35 +373,15 @@ struct Provider {
   subdirs: Vec<Subdir>,
   selected_index: usize,
   exit: bool,
+  path: String,       // listed directory
+  show_hidden: bool,
 }
 trait ProviderFn {
   fn getlines(&mut self) -> Vec<String>;
 }
 impl ProviderFn for Provider{
   fn getlines(&mut self) -> Vec<String> {
-    let mut max = 0;
-    for i in self.clone().subdirs {
-      if max<i.name.len() {
-        max = i.name.len();
-      }
-    }
-    max*=2;
-    /*print!("{esc}[0G", esc = 27 as char);
-    print!("{esc}[2K", esc = 27 as char);
-    for i in 0..self.subdirs.len() {
-      print!("{esc}[1A", esc = 27 as char);
-      print!("{esc}[2K", esc = 27 as char);
-    }*/
-    let mut output = String::new();
-    for i in self.subdirs.clone() {
-      if i.selected {
-        output += &format!("{line}{}{line}", i.name, line=vec!['-'; ((max - i.name.len())/2) as usize].into_iter().collect::<String>());
-      } else {
-        output += &format!("{space}{}{space}", i.name, space=vec![' '; ((max-i.name.len())/2) as usize].into_iter().collect::<String>());
-      }
-      output += "\n";
-    };
-    output.split("\n").collect::<Vec<&str>>().into_iter().map(|x| x.to_string()).collect::<Vec<String>>()
+    self.subdirs.iter().map(explorer::line).collect()
   }
 }
 
@@ -291,6 +393,7 @@ enum BufferType {
   File,
   Terminal,
   Directory(Provider),
+  Hex(Vec<u8>), // the bytes, `lines` shows them as a grid (see hex.rs)
 }
 #[derive(Debug,Clone,PartialEq)]
 struct EditorBuffer {
@@ -304,6 +407,13 @@ struct EditorBuffer {
   buf_name: String,
   save_path: String,
   saved: bool,
+  format: encoding::Format,       // line ending and encoding the file is written with
+  saved_format: encoding::Format, // ... and the one it has on disk
+  large: Option<large::LargeFile>, // only a window of the file is in `lines`, see large.rs
+  disk: Option<watch::Stamp>,      // the file as it was last read or written, see watch.rs
+  read_only: bool,                 // no write permission, `view` / `-R` or a large file: no input state, no `w`
+  changes: Option<changes::Changes>, // diff against the saved version for the gutter, see changes.rs
+  git: Option<git::GitFile>,         // repository of the file, see git.rs
 }
 #[derive(Debug,Clone,PartialEq)]
 enum State {
@@ -324,6 +434,19 @@ pub struct Program {
   io_cursor: u32,               // location of cursor in IO (x)
   io_history: Vec<String>,      // history of used commands to scroll via arrows
   io_history_index: usize,      // index of history
+  history_prefix: String,       // what was typed before browsing history, Up only shows commands starting with it
+  history_search: Option<(String, usize, String)>, // ctrl-r search: (query, matches skipped, io before searching)
+  completion: Option<completion::Completion>, // Tab menu of the IO line
+  pending_keys: Vec<KeyEvent>,  // keys of a keybind sequence typed so far
+  tab_offset: usize,            // first buffer shown in the tab bar
+  swaps: HashMap<String, Option<u64>>, // swap files by edited path: hash of the text swapped last, None if it belongs to another FokEdit
+  last_swap: std::time::Instant,
+  recovery: Vec<String>,        // paths with a stale swap file waiting for an answer
+  confirm: Option<(String, PathBuf)>, // y/n question in io and the path deleted on `y`
+  tree: Option<tree::Tree>,     // tree explorer sidebar
+  finder: Option<finder::Finder>, // fuzzy file finder, takes over the buffer area and the keys
+  blame: Option<(u64, String)>, // `blame` of the cursor's line while toggled on: (what it was taken for, text)
+  statusline_cache: Vec<(String, String, std::time::Instant)>, // per segment (signature, value, evaluated at)
   exit: bool,                   // whether to exit at the end of loop
 
   config: FokEditConfig,
@@ -331,16 +454,21 @@ pub struct Program {
 trait Editor {
   fn evaluate_io(&mut self) -> String;              // evaluate terminal line; use modified foklang for that
   fn display(&mut self);
+  fn statusline(&mut self, width: usize) -> String;
+  fn tab_bar(&mut self, width: usize) -> String;
   fn clear(&mut self);
   fn get_buffer(&mut self) -> &mut EditorBuffer;
   fn move_cursor(&mut self, vector: (i32, i32));
   fn move_selection(&mut self, vector: (i32, i32));
   fn move_io_cursor(&mut self, vector: i32);
   fn write_string(&mut self, string: String);
+  fn insert_text(&mut self, text: String);
+  fn editable(&mut self) -> bool;
 
   fn open(&mut self, fname: String);
   fn close(&mut self, id: usize);
   fn reload(&mut self);
+  fn check_swap(&mut self, fname: String);
 }
 impl Editor for Program {
   fn reload(&mut self) {
@@ -528,8 +656,26 @@ impl Editor for Program {
   theme = presets.minimal;
   ops = {{
     tab_size = 4;
+    kitty_keyboard = false;                     #! use the kitty keyboard protocol if the terminal supports it
+    leader = \"space\";                           #! what <leader> stands for in keybind keys
+    keybind_timeout = 1000;                     #! ms to wait for the next key of a sequence like \"g g\"
+    backup = \"none\";                          #! keep the previous version on save: \"none\", \"tilde\" (file~) or \"timestamp\" (file.20260101-120000~)
+    swap_interval = 4000;                       #! ms between swap file updates (~/.local/state/FokEdit/swap), 0 disables them
+    auto_session = false;                       #! reopen the last session of the directory when started without files
+    large_file = 64;                            #! files over this many MB open read-only without reading them in, 0 disables
+    git = true;                                 #! gutter signs against HEAD (line_numbers.git_signs), `blame`, `stage_hunk` and `reset_hunk` in git repositories
+    explorer = {{
+      dirs_first = true;
+      show_hidden = false;                      #! `.` toggles hidden files in an explorer buffer
+      keep_open = false;                        #! opening a file keeps the explorer buffer around
+      trash = \"\";                              #! `remove` moves entries to this directory instead of deleting them
+      tree_width = 30;                          #! `tree` shows a sidebar of this many columns
+      follow = true;                            #! the sidebar follows the active buffer's file
+    }};
     line_numbers = {{
       enable = false;
+      signs = true;                             #! mark lines added (+), modified (~) and deleted (_) since the last save
+      git_signs = true;                         #! and, in a column before them, since HEAD in git repositories
     }};
   }};
   elements = {{
@@ -549,7 +695,7 @@ cy = program.cursor.y; #! basically, a shortcut
   }};
   keybinds = [ 
     {{
-      key = \"ctrl_left\";                        #! ctrl_left --> ctrl + left_arrow combination #! due to budget you are not able to do stuff like ctrl_shift_left etc.
+      key = \"ctrl_left\";                        #! ctrl_left --> ctrl + left_arrow combination #! modifiers combine: ctrl_shift_left, alt_x, ctrl_home, f5...
       action = \"mb (0-1)\";                      #! foklang command, look at fokedit+foklang documentation for reference
       override = true;                          #! override default left_arrow action
       states = [states.control states.select];  #! states in which the keybind is valid (ex. don't move buffers with this keybind in `input` and `command` state)
@@ -560,29 +706,46 @@ cy = program.cursor.y; #! basically, a shortcut
       override = true;
       states = [states.control states.select];
     }}
+    {{key=\"<leader>w\"; action=\"w\";override=true;states=[states.control];}}                #! sequences: \"<leader>ff\", \"g g\", \"ctrl+shift+p\"
+    {{key=\"<leader>e\"; action=\"tree_focus\";override=true;states=[states.control];}}       #! tree explorer sidebar, `tree` hides it
+    {{key=\"ctrl_r\"; action=\"reload\";override=true;states=[states.control states.input states.select];when=\"program.buffer.type == \\\"file\\\"\";}} #! `when` - optional foklang predicate
     {{key=\"shift_right\"; action=\"select\";override=false;states=states.all;}}
     {{key=\"shift_left\"; action=\"select\";override=false;states=states.all;}}
     {{key=\"shift_up\"; action=\"select\";override=false;states=states.all;}}
     {{key=\"shift_down\"; action=\"select\";override=false;states=states.all;}}
   ];
-
+  statusline = {{                               #! segments are foklang expressions, leave both lists empty for the plain io bar
+    left = [
+      {{text = \"program.state\"; foreground = rgb 20 20 20; background = rgb 150 150 200;}}
+      {{text = \"program.buffer.name\";}}
+      {{text = \"if (program.buffer.saved == false) \\\"[+]\\\"\";}}
+    ];
+    right = [
+      {{text = \"program.buffer.branch\";}}          #! a segment can also take `interval` (ms) - re-evaluate on a timer, for slow expressions like exec
+      {{text = \"program.buffer.filetype\";}}
+      {{text = \"join program.buffer.encoding (join \\\" \\\" program.buffer.line_ending)\";}}
+      {{text = \"join (toString (program.cursor.y+1)) (join \\\":\\\" (toString (program.cursor.x+1)))\";}}
+      {{text = \"join (toString program.cursor.percent) \\\"%\\\"\";}}
+    ];
+  }};
 }}", presets = &(env::var("HOME").unwrap() + "/.config/FokEdit/presets.fok"))).unwrap();
     }
     let panics = std::panic::catch_unwind(|| {
       let raw = self.foklang.clone().raw_run(String::from("rgb x y z = x:(y:[z]); states = {control=0; command=1; input=2; select=3; all=[0..3]};") 
       + &fs::read_to_string(&(env::var("HOME").unwrap() + "/.config/FokEdit/configuration.fok")).unwrap(), self.clone());
 
-      foklang::core::builtins::load_fokedit_config(foklang::core::builtins::Arguments { function: foklang::core::builtins::FunctionArgs::singleProgram(raw, self.clone()) })
+      (raw.clone(), foklang::core::builtins::load_fokedit_config(foklang::core::builtins::Arguments { function: foklang::core::builtins::FunctionArgs::singleProgram(raw, self.clone()) }))
     });
     
-    if panics.is_ok() { /* safety layer */
-      let ran = panics.unwrap();
+    let mut config = None;
+    if let Ok((raw, ran)) = panics { /* safety layer */
       match ran.value {
         foklang::core::AST::Fructa::ProgramModifier(nprog) => {
           *self = nprog;
         }
         _ => {}
       }
+      config = Some(raw);
     } else {
       self.io = "Error: Can't evaluate config file!!".to_string();
     }
@@ -605,23 +768,42 @@ cy = program.cursor.y; #! basically, a shortcut
     } else {
       self.io = String::from("Foklang/FokEdit RC failed to evaluate");
     }
+    /* the config record, readable (and completed) as `config` on the IO line unless the rc took the name */
+    let name = foklang::core::AST::Node{kind: foklang::core::AST::NodeKind::Identifier{symbol: String::from("config"), childs: vec![]}};
+    if let Some(config) = config.filter(|_| !self.foklang.env.exists(name.clone())) {
+      self.foklang.env.declare(name, config);
+    }
   }
   /* vital editor's functions */
+  /* a swap file left behind for a file being opened - either another FokEdit is editing it or it's from a crash */
+  fn check_swap(&mut self, fname: String) {
+    let swap = match swap::read(&fname) {
+      Some(swap) => swap,
+      None => return,
+    };
+    if swap::in_use(&swap) {
+      self.swaps.insert(fname.clone(), None);
+      self.io = format!("{} is being edited by another FokEdit (pid {})", fname, swap.pid);
+    } else if swap.lines == self.buffers.last().unwrap().lines {
+      swap::remove(&fname); // nothing to recover
+    } else if !self.recovery.contains(&fname) {
+      self.recovery.push(fname);
+      if let Some(prompt) = swap::prompt(self) {
+        self.io = prompt;
+      }
+    }
+  }
   fn close(&mut self, id: usize) {
     self.buffers.remove(id);
+    if self.current > id || self.current >= self.buffers.len() {
+      self.current = self.current.saturating_sub(1);
+    }
   }
   fn open(&mut self, fname: String) {
     let fname = fname.replace("~", &env::var("HOME").unwrap());
     if Path::new(&fname).exists() {
       if Path::new(&fname).is_dir() {
-        let mut prov = Provider { subdirs: vec![], selected_index: 0, exit: false };
-
-        let mut first = true;
-        for i in fs::read_dir(Path::new(&fname)).unwrap() {
-          let abs = i.unwrap().path().to_str().unwrap().to_string();
-          prov.subdirs.push(Subdir { name: abs.split("/").collect::<Vec<&str>>().last().unwrap().to_string(), abs_path: abs, selected: first });
-          first = false;
-        }
+        let mut prov = explorer::read(&fname, &self.config.ops.explorer, self.config.ops.explorer.show_hidden);
         let lines = prov.getlines();
         self.buffers.push(
           EditorBuffer {
@@ -635,23 +817,102 @@ cy = program.cursor.y; #! basically, a shortcut
             buf_name: fname.clone(),
             save_path: fname,
             saved: true,
+            format: Default::default(),
+            saved_format: Default::default(),
+            large: None,
+            disk: None,
+            read_only: false,
+            changes: None,
+            git: None,
           }
         );
+      } else if large::is_large(&fname, self) {
+        match large::open(&fname) {
+          Ok((lines, large)) => {
+            self.buffers.push(
+              EditorBuffer {
+                cursor: (0, 0),
+                selection: ((0,0), (0,0)),
+                old_lines: lines.clone(),
+                lines,
+                buf_type: BufferType::File,
+                display_start_line: 0,
+                display_offset_collumn: 0,
+                buf_name: fname.clone(),
+                save_path: fname,
+                saved: true,
+                format: Default::default(),
+                saved_format: Default::default(),
+                large: Some(large),
+                disk: None,
+                read_only: true,
+                changes: None,
+                git: None,
+              }
+            );
+            self.io = String::from("large file, opened read-only");
+          },
+          Err(e) => self.io = e,
+        }
       } else {
+        let bytes = match fs::read(&fname) {
+          Ok(b) => b,
+          Err(e) => {
+            self.io = format!("couldn't read {}: {}", fname, e);
+            return;
+          }
+        };
+        let (lines, format) = encoding::decode(&bytes);
+        let disk = watch::stamp(&fname, &bytes);
+        let read_only = !files::writable(&fname);
+        if hex::is_binary(&bytes, &format) {
+          let lines = hex::render(&bytes);
+          self.buffers.push(
+            EditorBuffer {
+              cursor: (0, 0),
+              selection: ((0,0), (0,0)),
+              old_lines: lines.clone(),
+              lines,
+              buf_type: BufferType::Hex(bytes),
+              display_start_line: 0,
+              display_offset_collumn: 0,
+              buf_name: fname.clone(),
+              save_path: fname,
+              saved: true,
+              format: Default::default(),
+              saved_format: Default::default(),
+              large: None,
+              disk,
+              read_only,
+              changes: None,
+              git: None,
+            }
+          );
+          hex::snap(self);
+          return;
+        }
         self.buffers.push(
           EditorBuffer {
             cursor: (0, 0),
             selection: ((0,0), (0,0)),
-            lines: fs::read_to_string(fname.clone()).unwrap().split("\n").collect::<Vec<&str>>().into_iter().map(|x| String::from(x)).collect::<Vec<String>>(),
-            old_lines: fs::read_to_string(fname.clone()).unwrap().split("\n").collect::<Vec<&str>>().into_iter().map(|x| String::from(x)).collect::<Vec<String>>(),
+            old_lines: lines.clone(),
+            lines,
             buf_type: BufferType::File,
             display_start_line: 0,
             display_offset_collumn: 0,
             buf_name: fname.clone(),
-            save_path: fname,
+            save_path: fname.clone(),
             saved: true,
+            saved_format: format.clone(),
+            format,
+            large: None,
+            disk,
+            read_only,
+            changes: None,
+            git: if self.config.ops.git {git::open(&fname)} else {None},
           }
         );
+        self.check_swap(fname);
       }
     } else {
       self.buffers.push(
@@ -664,21 +925,30 @@ cy = program.cursor.y; #! basically, a shortcut
           display_start_line: 0,
           display_offset_collumn: 0,
           buf_name: fname.clone(),
-          save_path: fname,
-          saved: false
+          save_path: fname.clone(),
+          saved: false,
+          format: Default::default(),
+          saved_format: Default::default(),
+          large: None,
+          disk: None,
+          read_only: false,
+          changes: None,
+          git: None,
         }
       );
+      self.check_swap(fname);
     }
   }
   fn evaluate_io(&mut self) -> String {
-    self.io_history.push(self.io.clone());
+    history::push(self, self.io.clone());
     let mut ch = self.io.chars();
     ch.next();
 
     let foklang = Arc::new(Mutex::new(self.foklang.clone()));
     let panics = std::panic::catch_unwind(|| {
       let mut lock = foklang.lock();
-      let (program,io) = lock.as_mut().unwrap().run(ch.collect::<String>(), self.clone()); // foklang.run returns display of returned value from foklang code
+      let line = foklang::core::builtins::expand_alias(&ch.collect::<String>());
+      let (program,io) = lock.as_mut().unwrap().run(line, self.clone()); // foklang.run returns display of returned value from foklang code
       //drop(foklang);
       drop(lock);
       (program,io)}
@@ -697,75 +967,60 @@ cy = program.cursor.y; #! basically, a shortcut
     }
   }
   fn clear(&mut self) {
-    print!("\x1b[?47l\x1b 8");
+    if self.config.ops.kitty_keyboard {
+      print!("\x1b[<u");
+    }
+    print!("\x1b[?2004l\x1b[?47l\x1b 8");
     let _ = io::stdout().flush();
   }
   fn display(&mut self) { // scary math
-    let temp_lines = self.get_buffer().lines.clone();
-    if temp_lines == self.get_buffer().old_lines {
-      self.get_buffer().saved = true;
-    } else {
-      self.get_buffer().saved = false;
-    }
-    drop(temp_lines);
+    let modified = self.get_buffer().modified();
+    self.get_buffer().saved = !modified;
     let (tery, terx) = (get_terminal_size().unwrap().rows,  get_terminal_size().unwrap().cols);
     let mut result = String::new();
     result += "\x1b[2J\x1b[H";
-    let max_buf_display_len = 16;
-    let active_buffer_color = self.config.colors.active_buffer;
-    let inactive_buffer_color = self.config.colors.inactive_buffer;
-    for i in 0..self.buffers.len() {
-      if i == self.current {
-        result+=&format!("\x1b[48;2;{active_buffer_color}m\x1b[1m");
-      } else {
-        result+=&format!("\x1b[48;2;{inactive_buffer_color}m\x1b[22m");
-      }
-      
-      let mut buf_name = self.buffers[i].buf_name.clone();
-      while buf_name.len() > max_buf_display_len {
-        let mut buf_ch = buf_name.chars();
-        buf_ch.next();
-        buf_name = buf_ch.collect::<String>();
-      }
-      if buf_name.len() == max_buf_display_len {
-        let mut buf_ch = buf_name.chars();
-        buf_ch.next();
-        buf_ch.next();
-        buf_name = buf_ch.collect::<String>();
-      }
-      if !self.buffers[i].saved {
-        buf_name += "*";
-      }
-      result += &(vec![" "; ((max_buf_display_len - buf_name.len()) as f32 / 2.0).floor() as usize].into_iter().collect::<String>() + &buf_name + &vec![" "; ((max_buf_display_len - buf_name.len()) as f32 / 2.0).ceil() as usize].into_iter().collect::<String>());
-    }
+    result += &self.tab_bar(terx as usize);
     
-    let border_color = self.config.colors.border;
     let background_color = self.config.colors.background;
     let foreground_color = self.config.colors.foreground;
 
     let selection_color = self.config.colors.selection_color;
 
-    result += &format!("\x1b[22m\x1b[48;2;{border_color}m");
-    result += &(vec![" "; terx as usize - self.buffers.len()*max_buf_display_len]).into_iter().collect::<String>();
     //result +=  "\x1b[0m";
+    let area_start = result.len(); // buffer rows, the tree sidebar goes in front of them
     result += &format!("\x1b[38;2;{foreground_color}m\x1b[48;2;{background_color}m");
     //let mut display_sl = 0 as u32;
     let free_y = tery-3;
+    let sidebar = tree::width(self, terx);
+    let area_x = terx - sidebar;
 
     
     let line_numbers = self.config.ops.line_numbers.enable;
     let line_nums_background = self.config.ops.line_numbers.background;
     let line_nums_foreground = self.config.ops.line_numbers.foreground;
     
-    let mut free_x = terx;
+    let mut free_x = area_x;
     if line_numbers {
-      let len = self.get_buffer().lines.len().to_string().len();
+      let buffer = self.get_buffer();
+      let len = buffer.large.as_ref().map(|x| x.total_lines()).unwrap_or(buffer.lines.len()).to_string().len();
       if len<4 {
         free_x -= 4;
       } else {
         free_x -= len as u16 + 1;
       }
+      if self.config.ops.line_numbers.signs {
+        free_x -= 1;
+      }
     }
+    let lines = self.get_buffer().lines.len();
+    let signs = if line_numbers && self.config.ops.line_numbers.signs {
+      changes::signs(changes::update(self.get_buffer()), lines)
+    } else {vec![]};
+    let git_signs = if line_numbers && self.config.ops.line_numbers.git_signs && self.get_buffer().git.is_some() {
+      free_x -= 1;
+      changes::signs(git::head_hunks(self.get_buffer()), lines)
+    } else {vec![]};
+    let sign_colors = (self.config.ops.line_numbers.added, self.config.ops.line_numbers.modified, self.config.ops.line_numbers.deleted);
 
 
     if self.get_buffer().cursor.1 > (free_y-1) as u32 + self.get_buffer().display_start_line {
@@ -812,23 +1067,50 @@ cy = program.cursor.y; #! basically, a shortcut
       selection.0.0 = s10;
     }
 
+    // `blame` of the cursor's line, written after it
+    let cursor_y = self.get_buffer().cursor.1 as usize;
+    let blame = self.blame.as_ref().map(|x| (cursor_y, x.1.clone())).filter(|x| !x.1.is_empty());
+
+    // foreground of every line, directories stand out in the explorer
+    let directory_color = self.config.colors.directory;
+    let first_line = large::offset(self.get_buffer()); // line number of lines[0]
+    let line_colors = match &self.get_buffer().buf_type {
+      BufferType::Directory(d) => d.subdirs.iter().map(|x| if x.is_dir {directory_color} else {foreground_color}).collect::<Vec<RGB>>(),
+      _ => vec![],
+    };
+
     if (self.get_buffer().lines.len() as u16) < (free_y + left as u16) {
       let rlen = self.get_buffer().lines.len();
       
       let mut line = left;
       for i in &self.get_buffer().lines[left..rlen] {
+        let i = &encoding::printable(i);
+        let row_start = result.len();
         if line_numbers {
 
           result += &format!("\x1b[38;2;{line_nums_foreground}m\x1b[48;2;{line_nums_background}m");
 
 
-          result += &vec![" "; (terx - free_x - (line+1).to_string().len() as u16 - 1) as usize].into_iter().collect::<String>();
-          result += &(line+1).to_string();
+          let number = line + 1 + first_line;
+          let mut pad = (area_x - free_x - number.to_string().len() as u16 - 1) as usize;
+          if !git_signs.is_empty() {
+            result += &changes::gutter(git_signs.get(line), sign_colors, line_nums_foreground);
+            pad -= 1;
+          }
+          if !signs.is_empty() {
+            result += &changes::gutter(signs.get(line), sign_colors, line_nums_foreground);
+            pad -= 1;
+          }
+          result += &vec![" "; pad].into_iter().collect::<String>();
+          result += &number.to_string();
           result += " ";
 
           //reset color
           result += &format!("\x1b[38;2;{foreground_color}m\x1b[48;2;{background_color}m");
         }
+        if let Some(color) = line_colors.get(line) {
+          result += &format!("\x1b[38;2;{color}m");
+        }
         if offset > 0 {
           if i.len()  > offset {
             let max = std::cmp::min(offset+free_x as usize, i.len());
@@ -937,6 +1219,12 @@ cy = program.cursor.y; #! basically, a shortcut
             }
           }
         }
+        if let Some((y, text)) = &blame {
+          if *y == line {
+            let row = result.split_off(row_start);
+            result += &git::inline(row, text, line_nums_foreground, foreground_color);
+          }
+        }
         line += 1;
       }
 
@@ -948,7 +1236,7 @@ cy = program.cursor.y; #! basically, a shortcut
       for _ in 0..(((free_y) as u16) - (rlen - left) as u16) {
         if line_numbers {
           result += &format!("\x1b[38;2;{line_nums_foreground}m\x1b[48;2;{line_nums_background}m");
-          result += &vec![" "; (terx-free_x) as usize].into_iter().collect::<String>();
+          result += &vec![" "; (area_x-free_x) as usize].into_iter().collect::<String>();
 
           //reset color
           result += &format!("\x1b[38;2;{empty_line_foreground}m\x1b[48;2;{empty_line_background}m");
@@ -962,15 +1250,30 @@ cy = program.cursor.y; #! basically, a shortcut
       //let reallen = self.get_buffer().lines.len();
       let mut line = left;
       for i in &self.get_buffer().lines[left..left+(free_y) as usize] {
+        let i = &encoding::printable(i);
+        let row_start = result.len();
         if  line_numbers {
           result += &format!("\x1b[38;2;{line_nums_foreground}m\x1b[48;2;{line_nums_background}m");
-          result += &vec![" "; (terx - free_x - (line+1).to_string().len() as u16 - 1) as usize].into_iter().collect::<String>();
-          result += &(line+1).to_string();
+          let number = line + 1 + first_line;
+          let mut pad = (area_x - free_x - number.to_string().len() as u16 - 1) as usize;
+          if !git_signs.is_empty() {
+            result += &changes::gutter(git_signs.get(line), sign_colors, line_nums_foreground);
+            pad -= 1;
+          }
+          if !signs.is_empty() {
+            result += &changes::gutter(signs.get(line), sign_colors, line_nums_foreground);
+            pad -= 1;
+          }
+          result += &vec![" "; pad].into_iter().collect::<String>();
+          result += &number.to_string();
           result += " ";
 
           //reset color 
           result += &format!("\x1b[38;2;{foreground_color}m\x1b[48;2;{background_color}m");
         }
+        if let Some(color) = line_colors.get(line) {
+          result += &format!("\x1b[38;2;{color}m");
+        }
         if offset > 0 {
           if i.len()  > offset {
             let max = std::cmp::min(offset+free_x as usize, i.len());
@@ -985,6 +1288,12 @@ cy = program.cursor.y; #! basically, a shortcut
             result += &(i.to_owned()[..free_x as usize].to_owned() + "\n");
           }
         }
+        if let Some((y, text)) = &blame {
+          if *y == line {
+            let row = result.split_off(row_start);
+            result += &git::inline(row, text, line_nums_foreground, foreground_color);
+          }
+        }
         line+=1;
       }
     }
@@ -992,11 +1301,29 @@ cy = program.cursor.y; #! basically, a shortcut
       //    result += &(i.to_owned() + &vec![" "; free_x as usize - i.len() ].into_iter().collect::<String>() + "\n");
       //  }
 
+    if self.finder.is_some() {
+      result.truncate(area_start);
+      result += &finder::render(self, free_y as usize, terx as usize).concat();
+    } else if sidebar > 0 {
+      tree::follow(self);
+      let rows = tree::render(self, free_y as usize, sidebar as usize);
+      let area = result.split_off(area_start);
+      result += &tree::splice(area, rows);
+    }
+
     let io_background = self.config.colors.io_background.clone();
     let io_foreground = self.config.colors.io_foreground.clone();
 
     result += &format!("\x1b[38;2;{io_foreground}m\x1b[48;2;{io_background}m");
-    result += &(vec![" "; terx as usize]).into_iter().collect::<String>();
+    let custom_statusline = self.config.statusline.left.len() + self.config.statusline.right.len() > 0;
+    if let (Some(completion), State::Command) = (&self.completion, &self.state) {
+      result += &completion::menu(completion, terx as usize, io_foreground, io_background);
+    } else if custom_statusline {
+      result += &self.statusline(terx as usize);
+      result += &format!("\x1b[38;2;{io_foreground}m\x1b[48;2;{io_background}m");
+    } else {
+      result += &(vec![" "; terx as usize]).into_iter().collect::<String>();
+    }
     result += "\n";
 
 
@@ -1009,26 +1336,35 @@ cy = program.cursor.y; #! basically, a shortcut
     let col = self.get_buffer().display_offset_collumn;
 
     let cursor_string;
-    if self.config.elements.debug.cursor {
+    if self.config.elements.debug.cursor && !custom_statusline {
       cursor_string = format!("{}:{}:;:{}:{};", col, self.get_buffer().display_start_line, c.0, c.1);
     } else {
       cursor_string = String::new();
     }
     let mode = match self.state {
+      _ if custom_statusline => "", // mode is up to the status line then
       State::Input => "Input",
       State::Control => "Control",
       State::Command => "Command",
       State::Selection => "Select",
     };
 
-    result += &(self.io.clone() + &(vec![" "; terx as usize - self.io.len() - mode.len() - cursor_string.len()]).into_iter().collect::<String>());
+    result += &(self.io.clone() + &(vec![" "; (terx as usize).saturating_sub(self.io.len() + mode.len() + cursor_string.len())]).into_iter().collect::<String>());
     result += &cursor_string;
     result +=  mode;
     match self.state {
+      _ if self.finder.is_some() => {
+        let column = self.io_cursor+1;
+        result += &format!("\x1b[{tery};{column}H");
+      },
       State::Command => {
         let column = self.io_cursor+1;
         result += &format!("\x1b[{tery};{column}H");
       },
+      _ if sidebar > 0 && self.tree.as_ref().unwrap().focus => {
+        let tree = self.tree.as_ref().unwrap();
+        result += &format!("\x1b[{line};1H", line=tree.selected - tree.offset + 2);
+      },
       _ => {
         let column = self.get_buffer().cursor.0+1 - self.get_buffer().display_offset_collumn + (terx-free_x) as u32;
         result += &format!("\x1b[{line};{column}H", line=self.get_buffer().cursor.1+2 - self.get_buffer().display_start_line);
@@ -1040,6 +1376,155 @@ cy = program.cursor.y; #! basically, a shortcut
   }
 
 
+  fn tab_bar(&mut self, width: usize) -> String {
+    let colors = self.config.colors.clone();
+    let tab_width = std::cmp::max(std::cmp::min(colors.buffer_width, width.saturating_sub(2)), 4);
+
+    /* shortest path suffix that tells buffers apart: `src/main.rs` and `tests/main.rs` instead of two `main.rs` */
+    let paths = self.buffers.iter().map(|x| x.buf_name.trim_end_matches('/').split('/').map(|x| x.to_string()).collect::<Vec<String>>()).collect::<Vec<Vec<String>>>();
+    let mut depth = vec![1; paths.len()];
+    let suffix = |path: &Vec<String>, depth: usize| path[path.len().saturating_sub(depth)..].join("/");
+    loop {
+      let names = paths.iter().zip(depth.iter()).map(|(p, d)| suffix(p, *d)).collect::<Vec<String>>();
+      let mut changed = false;
+      for i in 0..names.len() {
+        if depth[i] < paths[i].len() && names.iter().enumerate().any(|(n, x)| n != i && *x == names[i] && paths[n] != paths[i]) {
+          depth[i] += 1;
+          changed = true;
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+
+    /* keep `current` in view */
+    let needs_scroll = self.buffers.len() * tab_width > width;
+    let fits = std::cmp::max(if needs_scroll {width.saturating_sub(2)} else {width} / tab_width, 1);
+    if self.current < self.tab_offset {
+      self.tab_offset = self.current;
+    } else if self.current >= self.tab_offset + fits {
+      self.tab_offset = self.current + 1 - fits;
+    }
+    self.tab_offset = std::cmp::min(self.tab_offset, self.buffers.len().saturating_sub(fits));
+    let shown = self.tab_offset..std::cmp::min(self.tab_offset + fits, self.buffers.len());
+
+    let mut result = String::new();
+    let mut used = 0;
+    if needs_scroll {
+      let buffer_overflow = colors.buffer_overflow;
+      let border = colors.border;
+      result += &format!("\x1b[22m\x1b[38;2;{buffer_overflow}m\x1b[48;2;{border}m{}", if self.tab_offset > 0 {"<"} else {" "});
+      used += 1;
+    }
+    let buffer_foreground = colors.buffer_foreground;
+    result += &format!("\x1b[38;2;{buffer_foreground}m");
+    for i in shown.clone() {
+      if i == self.current {
+        result += &format!("\x1b[48;2;{}m\x1b[1m", colors.active_buffer);
+      } else {
+        result += &format!("\x1b[48;2;{}m\x1b[22m", colors.inactive_buffer);
+      }
+      let mut name = suffix(&paths[i], depth[i]);
+      if !self.buffers[i].saved {
+        name += "*";
+      }
+      if self.buffers[i].read_only {
+        name += " [RO]";
+      }
+      if colors.buffer_index {
+        name = format!("{} {}", i, name);
+      }
+      let mut chars = name.chars().collect::<Vec<char>>();
+      if chars.len() > tab_width - 2 { // cut from the front, the end of a path says more
+        chars = vec!['…'].into_iter().chain(chars[chars.len() - (tab_width - 3)..].iter().cloned()).collect();
+      }
+      let pad = tab_width - chars.len();
+      result += &(vec![" "; pad / 2].into_iter().collect::<String>() + &chars.into_iter().collect::<String>() + &vec![" "; pad - pad / 2].into_iter().collect::<String>());
+      used += tab_width;
+    }
+
+    let border = colors.border;
+    result += &format!("\x1b[22m\x1b[48;2;{border}m");
+    if needs_scroll {
+      result += &vec![" "; width.saturating_sub(used + 1)].into_iter().collect::<String>();
+      let buffer_overflow = colors.buffer_overflow;
+      result += &format!("\x1b[38;2;{buffer_overflow}m{}", if shown.end < self.buffers.len() {">"} else {" "});
+    } else {
+      result += &vec![" "; width.saturating_sub(used)].into_iter().collect::<String>();
+    }
+    result
+  }
+
+  fn statusline(&mut self, width: usize) -> String {
+    let segments = self.config.statusline.left.iter().map(|x| (x.clone(), false))
+      .chain(self.config.statusline.right.iter().map(|x| (x.clone(), true))).collect::<Vec<(StatusSegment, bool)>>();
+    let current = self.current;
+    let save_path = self.get_buffer().save_path.clone();
+    // segments see `program` as this record, so it is all their values can depend on (besides time, for `interval`)
+    let record = foklang::core::builtins::program_record(self);
+    let state = record.value.display();
+
+    self.statusline_cache.resize(segments.len(), (String::new(), String::new(), std::time::Instant::now()));
+    let mut left: Vec<(String, RGB, RGB)> = vec![];
+    let mut right: Vec<(String, RGB, RGB)> = vec![];
+    for (i, (segment, is_right)) in segments.into_iter().enumerate() {
+      let signature = if segment.interval > 0 {
+        format!("{}{}{}", segment.text, current, save_path)
+      } else {
+        segment.text.clone() + &state
+      };
+      let cached = &self.statusline_cache[i];
+      let expired = segment.interval > 0 && cached.2.elapsed().as_millis() >= segment.interval as u128;
+      if cached.0 != signature || expired {
+        let mut foklang = self.foklang.clone();
+        foklang.bind("program", record.clone());
+        let buffers = std::mem::take(&mut self.buffers); // the interpreter clones the program at every step, leave the text out
+        let program = self.clone();
+        self.buffers = buffers;
+        let panics = std::panic::catch_unwind(|| {
+          let value = foklang.raw_run(segment.text.clone(), program.clone()).value.display();
+          drop(foklang);
+          value
+        });
+        let value = match panics {
+          Ok(value) => value.trim().replace("\n", " "),
+          Err(_) => String::from("<error>"),
+        };
+        self.statusline_cache[i] = (signature, value, std::time::Instant::now());
+      }
+      let value = self.statusline_cache[i].1.clone();
+      if value.is_empty() {
+        continue;
+      }
+      let colors = (segment.foreground.unwrap_or(self.config.colors.io_foreground), segment.background.unwrap_or(self.config.colors.io_background));
+      if is_right {
+        right.push((format!(" {} ", value), colors.0, colors.1));
+      } else {
+        left.push((format!(" {} ", value), colors.0, colors.1));
+      }
+    }
+
+    let len = |x: &Vec<(String, RGB, RGB)>| x.iter().map(|s| s.0.chars().count()).sum::<usize>();
+    while len(&left) + len(&right) > width && !left.is_empty() {
+      left.pop();
+    }
+    while len(&right) > width && !right.is_empty() {
+      right.remove(0);
+    }
+    let io_background = self.config.colors.io_background;
+    let mut result = String::new();
+    for i in &left {
+      result += &format!("\x1b[38;2;{}m\x1b[48;2;{}m{}", i.1, i.2, i.0);
+    }
+    result += &format!("\x1b[48;2;{io_background}m");
+    result += &vec![" "; width - len(&left) - len(&right)].into_iter().collect::<String>();
+    for i in &right {
+      result += &format!("\x1b[38;2;{}m\x1b[48;2;{}m{}", i.1, i.2, i.0);
+    }
+    result
+  }
+
   fn get_buffer(&mut self) -> &mut EditorBuffer {
     return &mut self.buffers[self.current]
   }
@@ -1109,14 +1594,105 @@ cy = program.cursor.y; #! basically, a shortcut
     self.io_cursor = n0 as u32;
   }
   fn write_string(&mut self, string: String) {
+    if !self.editable() {
+      return;
+    }
     let index = (self.get_buffer().cursor.1) as usize;
     let x = self.get_buffer().cursor.0 as usize;
     let str1 = self.get_buffer().lines[index].chars().take(x).collect::<String>() + &string;
     self.get_buffer().lines[index] = str1 + &self.get_buffer().lines[index].chars().skip(x).collect::<String>();
     self.get_buffer().saved = false;
   }
+  fn insert_text(&mut self, text: String) { // multiline insert done as one edit (used by paste)
+    if !self.editable() {
+      return;
+    }
+    let index = self.get_buffer().cursor.1 as usize;
+    let x = self.get_buffer().cursor.0 as usize;
+    let line = self.get_buffer().lines[index].clone();
+    let left = line.chars().take(x).collect::<String>();
+    let right = line.chars().skip(x).collect::<String>();
+
+    let mut inserted = text.split("\n").map(|x| x.to_string()).collect::<Vec<String>>();
+    let last = inserted.len()-1;
+    let mut column = inserted[last].chars().count();
+    if last == 0 {
+      column += left.chars().count();
+    }
+    inserted[0] = left + &inserted[0];
+    inserted[last] += &right;
+    self.get_buffer().lines.splice(index..index+1, inserted);
+    self.get_buffer().cursor = (column as u32, (index+last) as u32);
+    self.get_buffer().saved = false;
+  }
+  fn editable(&mut self) -> bool { // false (and says so) for a read-only buffer, checked before any edit
+    let buffer = &self.buffers[self.current];
+    if buffer.read_only {
+      self.io = format!("{} is read-only", buffer.buf_name);
+    }
+    !buffer.read_only
+  }
 }
 impl EditorBuffer {
+  /* unsaved changes; directory listings never count */
+  fn modified(&self) -> bool {
+    match self.buf_type {
+      BufferType::Directory(_) => false,
+      _ => self.lines != self.old_lines || self.format != self.saved_format,
+    }
+  }
+  /* what gets written to disk */
+  fn contents(&self) -> Result<Vec<u8>, String> {
+    match &self.buf_type {
+      BufferType::Hex(bytes) => Ok(bytes.clone()),
+      _ => encoding::encode(&self.lines, &self.format),
+    }
+  }
+  fn filetype(&self) -> String {
+    match self.buf_type {
+      BufferType::File => {},
+      _ => return String::new(),
+    }
+    let name = self.save_path.split("/").last().unwrap_or("").to_string();
+    if !name.contains(".") {
+      return String::new();
+    }
+    match name.split(".").last().unwrap() {
+      "rs" => "rust",
+      "py" => "python",
+      "c" | "h" => "c",
+      "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+      "js" | "mjs" => "javascript",
+      "ts" => "typescript",
+      "nix" => "nix",
+      "fok" => "foklang",
+      "md" => "markdown",
+      "sh" | "bash" => "sh",
+      "txt" => "text",
+      "toml" => "toml",
+      "json" => "json",
+      "html" | "htm" => "html",
+      "css" => "css",
+      ext => ext,
+    }.to_string()
+  }
+  /* selection with start before end */
+  fn ordered_selection(&self) -> ((u32, u32), (u32, u32)) {
+    let (a, b) = self.selection;
+    if (a.1, a.0) <= (b.1, b.0) { (a, b) } else { (b, a) }
+  }
+  /* amount of selected characters, line breaks included */
+  fn selection_size(&self) -> usize {
+    let (start, end) = self.ordered_selection();
+    if start.1 == end.1 {
+      return (end.0 - start.0) as usize;
+    }
+    let mut size = self.lines[start.1 as usize].chars().count() - start.0 as usize + 1;
+    for i in start.1+1..end.1 {
+      size += self.lines[i as usize].chars().count() + 1;
+    }
+    size + end.0 as usize
+  }
   fn compile_text(&mut self) -> String {
     let mut result = String::new();
     for i in self.lines.clone() {
@@ -1130,490 +1706,694 @@ impl EditorBuffer {
 /* basic key events + keybinds */
 
 fn handle_key_event(program: &mut Program, event: KeyEvent) -> Program {
-  let (tery, terx) = (get_terminal_size().unwrap().rows,  get_terminal_size().unwrap().cols);
-  let mut overridek = false;
-  for i in program.config.keybinds.keybinds.clone() {
-    if i.0 == event && i.3.contains(&program.state) {
-      overridek = i.2;
-      let mut foklang = program.foklang.clone();
-
-      let panics = std::panic::catch_unwind(|| {
-        let (program,io) = foklang.run(i.1.clone(), program.clone()); // foklang.run returns display of returned value from foklang code
-        drop(foklang);
-        (program,io)
-      });
-      if panics.is_ok() {
-        let uw = panics.unwrap();
-        *program = uw.0;
-        program.io = uw.1
-      } else {
-        program.io = format!("Foklang panicked on keybind evaluation: {}.", i.1)
-      }
-    }
+  if program.history_search.is_some() {
+    history::search_key(program, event);
+    return program.clone();
+  }
+  if program.finder.is_some() {
+    finder::key(program, event);
+    return program.clone();
   }
-  if !overridek {
+  if !program.recovery.is_empty() && program.state == State::Control && program.pending_keys.is_empty() {
+    let here = swap::prompting_here(program) && event.modifiers.is_empty();
     match event.code {
-      KeyCode::Enter => {
-        match program.state {
-          State::Command => {
-            program.io = program.evaluate_io();
-            program.state = State::Control;
-          },
-          State::Input => {
-            match program.get_buffer().buf_type.clone() {
-              BufferType::File => {
-                let index = program.get_buffer().cursor.1;
-                let index2 = program.get_buffer().cursor.0;
-                let mut leftlist = program.get_buffer().lines[..index as usize].into_iter().map(|x| x.to_string()).collect::<Vec<String>>();
-                leftlist.push(program.get_buffer().lines[index as usize][..index2 as usize].to_string());
-                leftlist.push(program.get_buffer().lines[index as usize][index2 as usize..].to_string());
-                leftlist.append(&mut program.get_buffer().lines[index as usize+1..].into_iter().map(|x| x.to_string()).collect::<Vec<String>>());
-                program.get_buffer().lines = leftlist;
-                program.move_cursor((-i32::MAX, 1));
-              },
-              BufferType::Directory(d) => {
-                program.open(d.subdirs[d.selected_index].abs_path.clone());
-                program.close(program.current);
-                program.current = program.buffers.len()-1;
-                program.state = State::Control;
-              }
-              _ => {}
-            }
-          },
-          State::Control => {
-            match program.get_buffer().buf_type.clone() {
-              BufferType::File => {
-                program.move_cursor((0, 1));
-              },
-              BufferType::Directory(d) => {
-                program.open(d.subdirs[d.selected_index].abs_path.clone());
-                program.close(program.current);
-                program.current = program.buffers.len()-1;
-                program.state = State::Control;
-              },
-              _ => {}
-            }
-          },
-          State::Selection => {
-            program.move_cursor((0, 1)); // move selection also pls
-            program.move_selection((0,1));
-          },
-        }
+      KeyCode::Char(c) if here && ['r', 'd', 'x'].contains(&c) => {
+        swap::resolve(program, Some(c));
+        return program.clone();
       },
-      KeyCode::Escape => {
-        match program.state {
-          State::Command => {
-            program.state = State::Control;
-          },
-          State::Input => {
-            program.state = State::Control;
-          },
-          State::Control => {},
-          State::Selection => {
-            program.get_buffer().selection = ((0,0),(0,0));
-            program.state = State::Control;
-          },
-        }
+      KeyCode::Escape if here => {
+        swap::resolve(program, None);
+        return program.clone();
       },
-      KeyCode::Delete => {
-        match program.get_buffer().buf_type.clone() {
-          BufferType::File => {
+      _ => swap::resolve(program, None), // any other key is a "later", then does what it does
+    }
+  }
+  if let Some((_, path)) = program.confirm.take() {
+    match event.code {
+      KeyCode::Char('y') | KeyCode::Char('Y') => explorer::delete(program, &path),
+      _ => program.io = String::from("cancelled"),
+    }
+    return program.clone();
+  }
+  if program.pending_keys.is_empty() && tree::key(program, &event) {
+    return program.clone();
+  }
+  if program.pending_keys.is_empty() && hex::key(program, &event) {
+    return program.clone();
+  }
+  program.pending_keys.push(event);
+  resolve_pending_keys(program, false);
+  leave_read_only_input(program);
+  program.clone()
+}
 
-        match program.state {
-          State::Command => {
-            let mut ioc = program.io.chars().collect::<Vec<char>>();
-            if ioc.len() > program.io_cursor as usize {
-              ioc.remove(program.io_cursor as usize);
-              program.io = ioc.into_iter().collect::<String>();
-            }
-          },
-          State::Control => {
-            let index = (program.get_buffer().display_start_line + program.get_buffer().cursor.1) as usize;
-            let x = program.get_buffer().cursor.0 as usize;
-            let ic = program.get_buffer().lines[index].clone();
-            let ct = ic.chars().collect::<Vec<char>>()[..x].into_iter().collect::<String>();
-            let strc = ct.chars();
-            let ct2 = ic.chars().collect::<Vec<char>>()[x..].into_iter().collect::<String>();
-            let mut right = ct2.chars();
-
-            right.next();
-            program.get_buffer().lines[index] = strc.collect::<String>() + &right.collect::<String>();
-          },
-          State::Input => {
-            let index = (program.get_buffer().display_start_line + program.get_buffer().cursor.1) as usize;
-            let x = program.get_buffer().cursor.0 as usize;
-            let ic = program.get_buffer().lines[index].clone();
-            let ct = ic.chars().collect::<Vec<char>>()[..x].into_iter().collect::<String>();
-            let strc = ct.chars();
-            let ct2 = ic.chars().collect::<Vec<char>>()[x..].into_iter().collect::<String>();
-            let mut right = ct2.chars();
-            right.next();
-            program.get_buffer().lines[index] = strc.collect::<String>() + &right.collect::<String>();
-          },
-          State::Selection => {
-            let mut selection = program.get_buffer().selection;
-
-            if selection.1.1 < selection.0.1 {
-              let s0 = selection.0;
-              selection.0.1 = selection.1.1;
-              selection.0.0 = selection.1.0;
-              selection.1.1 = s0.1;
-              selection.1.0 = s0.0;
-            }
-            if selection.0.1 == selection.1.1 && selection.0.0 > selection.1.0 {
-              let s10 = selection.1.0;
-              selection.1.0 = selection.0.0;
-              selection.0.0 = s10;
+/* called when no key arrived within ops.keybind_timeout */
+fn flush_pending_keys(program: &mut Program) -> Program {
+  resolve_pending_keys(program, true);
+  leave_read_only_input(program);
+  program.clone()
+}
+
+/* edits to read-only buffers are refused up front (`editable`); this only drops input state
+   when a keybind switched from a writable buffer to a read-only one mid-typing */
+fn leave_read_only_input(program: &mut Program) {
+  if program.state == State::Input && !program.buffers.is_empty() && !program.editable() {
+    program.state = State::Control;
+  }
+}
+
+/* match the typed keys against keybinds; keys that can still grow into a longer sequence wait (unless flushing),
+   otherwise the longest complete keybind fires and keys belonging to no keybind get default handling */
+fn resolve_pending_keys(program: &mut Program, flush: bool) {
+  while !program.pending_keys.is_empty() {
+    let keys = program.pending_keys.clone();
+    let applies = applicable_binds(program, &keys[0]);
+    let valid = |id: usize| applies[id];
+    if !flush && program.config.keybinds.is_prefix(&keys, &valid) {
+      return;
+    }
+    let mut len = keys.len();
+    while len > 0 && program.config.keybinds.matching(&keys[..len], &valid).is_empty() {
+      len -= 1;
+    }
+    if len == 0 {
+      program.pending_keys.remove(0);
+      default_key_event(program, keys[0].clone());
+    } else {
+      program.pending_keys.drain(..len);
+      let binds = program.config.keybinds.matching(&keys[..len], &valid);
+      if !run_keybinds(program, binds) {
+        for key in keys[..len].iter().cloned() {
+          default_key_event(program, key);
+        }
+      }
+    }
+  }
+}
+
+/* what `when` predicates run against: the editor without its buffers, so nothing big gets cloned per key, and
+   `program` as the usual record of it */
+fn when_context(program: &mut Program) -> (foklang::foklang::Foklang, Program) {
+  let record = foklang::core::builtins::program_record(program);
+  let buffers = std::mem::take(&mut program.buffers);
+  let env = std::mem::take(&mut program.foklang.env);
+  let context = program.clone();
+  program.buffers = buffers;
+  program.foklang.env = env;
+  let mut foklang = context.foklang.clone();
+  foklang.env = foklang::core::env::Environment{parent: Some(Box::new(program.foklang.env.clone())), error_handler: program.foklang.env.error_handler, ..Default::default()};
+  foklang.env.declare(foklang::core::AST::Node{kind: foklang::core::AST::NodeKind::Identifier{symbol: String::from("program"), childs: vec![]}}, record);
+  (foklang, context)
+}
+
+fn when(foklang: &foklang::foklang::Foklang, context: &Program, predicate: &str) -> bool {
+  let mut foklang = foklang.clone();
+  let (context, predicate) = (context.clone(), predicate.to_string());
+  let panics = std::panic::catch_unwind(move || foklang.raw_run(predicate, context).value);
+  matches!(panics, Ok(foklang::core::AST::Fructa::Condicio(true)))
+}
+
+/* per keybind, whether it can fire on keys starting with `first`: valid in the current state and its `when`
+   predicate (if any) evaluates to true */
+fn applicable_binds(program: &mut Program, first: &KeyEvent) -> Vec<bool> {
+  let state = program.state.clone();
+  let candidates = program.config.keybinds.keybinds.iter().map(|x| x.0.first() == Some(first) && x.3.contains(&state)).collect::<Vec<bool>>();
+  if !program.config.keybinds.keybinds.iter().zip(&candidates).any(|(x, candidate)| *candidate && !x.4.is_empty()) {
+    return candidates;
+  }
+  let (foklang, context) = when_context(program);
+  program.config.keybinds.keybinds.iter().zip(candidates).map(|(x, candidate)| candidate && (x.4.is_empty() || when(&foklang, &context, &x.4))).collect()
+}
+
+/* keybind `id` still applies, checked again before it runs since earlier binds may have changed things */
+fn keybind_applies(program: &mut Program, id: usize) -> bool {
+  let bind = program.config.keybinds.keybinds[id].clone();
+  if !bind.3.contains(&program.state) {
+    return false;
+  }
+  if bind.4.is_empty() {
+    return true;
+  }
+  let (foklang, context) = when_context(program);
+  when(&foklang, &context, &bind.4)
+}
+
+/* returns whether default handling of the keys is overriden */
+fn run_keybinds(program: &mut Program, binds: Vec<usize>) -> bool {
+  let mut overridek = false;
+  for id in binds {
+    let i = program.config.keybinds.keybinds[id].clone();
+    if keybind_applies(program, id) {
+      overridek = i.2;
+      run_action(program, i.1);
+    }
+  }
+  overridek
+}
+
+fn run_action(program: &mut Program, action: String) {
+  let mut foklang = program.foklang.clone();
+
+  let panics = std::panic::catch_unwind(|| {
+    let (program,io) = foklang.run(action.clone(), program.clone()); // foklang.run returns display of returned value from foklang code
+    drop(foklang);
+    (program,io)
+  });
+  if panics.is_ok() {
+    let uw = panics.unwrap();
+    *program = uw.0;
+    program.io = uw.1
+  } else {
+    program.io = format!("Foklang panicked on keybind evaluation: {}.", action)
+  }
+}
+
+fn default_key_event(program: &mut Program, event: KeyEvent) {
+  let (tery, terx) = (get_terminal_size().unwrap().rows,  get_terminal_size().unwrap().cols);
+  if program.state == State::Command && !matches!(event.code, KeyCode::Arrow(Direction::Up) | KeyCode::Arrow(Direction::Down)) {
+    program.io_history_index = 0; // editing the line ends history browsing
+  }
+  if event.code != KeyCode::Tab {
+    program.completion = None;
+  }
+  match event.code {
+    KeyCode::Enter => {
+      match program.state {
+        State::Command => {
+          program.io = program.evaluate_io();
+          program.state = State::Control;
+        },
+        State::Input => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File => {
+              let index = program.get_buffer().cursor.1;
+              let index2 = program.get_buffer().cursor.0;
+              let mut leftlist = program.get_buffer().lines[..index as usize].into_iter().map(|x| x.to_string()).collect::<Vec<String>>();
+              leftlist.push(program.get_buffer().lines[index as usize][..index2 as usize].to_string());
+              leftlist.push(program.get_buffer().lines[index as usize][index2 as usize..].to_string());
+              leftlist.append(&mut program.get_buffer().lines[index as usize+1..].into_iter().map(|x| x.to_string()).collect::<Vec<String>>());
+              program.get_buffer().lines = leftlist;
+              program.move_cursor((-i32::MAX, 1));
+            },
+            BufferType::Directory(_) => {
+              explorer::open_selected(program);
             }
+            _ => {}
+          }
+        },
+        State::Control => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File if grep::is_results(program.get_buffer()) => {
+              grep::jump(program);
+            },
+            BufferType::File => {
+              program.move_cursor((0, 1));
+            },
+            BufferType::Directory(_) => {
+              explorer::open_selected(program);
+            },
+            _ => {}
+          }
+        },
+        State::Selection => {
+          program.move_cursor((0, 1)); // move selection also pls
+          program.move_selection((0,1));
+        },
+      }
+    },
+    KeyCode::Escape => {
+      match program.state {
+        State::Command => {
+          program.state = State::Control;
+        },
+        State::Input => {
+          program.state = State::Control;
+        },
+        State::Control => {},
+        State::Selection => {
+          program.get_buffer().selection = ((0,0),(0,0));
+          program.state = State::Control;
+        },
+      }
+    },
+    KeyCode::Delete if matches!(program.state, State::Control | State::Selection) && !program.editable() => {},
+    KeyCode::Delete => {
+      match program.get_buffer().buf_type.clone() {
+        BufferType::File => {
+
+      match program.state {
+        State::Command => {
+          let mut ioc = program.io.chars().collect::<Vec<char>>();
+          if ioc.len() > program.io_cursor as usize {
+            ioc.remove(program.io_cursor as usize);
+            program.io = ioc.into_iter().collect::<String>();
+          }
+        },
+        State::Control => {
+          let index = (program.get_buffer().display_start_line + program.get_buffer().cursor.1) as usize;
+          let x = program.get_buffer().cursor.0 as usize;
+          let ic = program.get_buffer().lines[index].clone();
+          let ct = ic.chars().collect::<Vec<char>>()[..x].into_iter().collect::<String>();
+          let strc = ct.chars();
+          let ct2 = ic.chars().collect::<Vec<char>>()[x..].into_iter().collect::<String>();
+          let mut right = ct2.chars();
+
+          right.next();
+          program.get_buffer().lines[index] = strc.collect::<String>() + &right.collect::<String>();
+        },
+        State::Input => {
+          let index = (program.get_buffer().display_start_line + program.get_buffer().cursor.1) as usize;
+          let x = program.get_buffer().cursor.0 as usize;
+          let ic = program.get_buffer().lines[index].clone();
+          let ct = ic.chars().collect::<Vec<char>>()[..x].into_iter().collect::<String>();
+          let strc = ct.chars();
+          let ct2 = ic.chars().collect::<Vec<char>>()[x..].into_iter().collect::<String>();
+          let mut right = ct2.chars();
+          right.next();
+          program.get_buffer().lines[index] = strc.collect::<String>() + &right.collect::<String>();
+        },
+        State::Selection => {
+          let mut selection = program.get_buffer().selection;
+
+          if selection.1.1 < selection.0.1 {
+            let s0 = selection.0;
+            selection.0.1 = selection.1.1;
+            selection.0.0 = selection.1.0;
+            selection.1.1 = s0.1;
+            selection.1.0 = s0.0;
+          }
+          if selection.0.1 == selection.1.1 && selection.0.0 > selection.1.0 {
+            let s10 = selection.1.0;
+            selection.1.0 = selection.0.0;
+            selection.0.0 = s10;
+          }
 
 
-            if selection.0.1 == selection.1.1 {
-              for i in (selection.0.0 as usize .. selection.1.0 as usize).rev() {
-                program.get_buffer().lines[selection.0.1 as usize].remove(i);
-              }
-            } else {
+          if selection.0.1 == selection.1.1 {
+            for i in (selection.0.0 as usize .. selection.1.0 as usize).rev() {
+              program.get_buffer().lines[selection.0.1 as usize].remove(i);
+            }
+          } else {
 
-              for i in (selection.0.0 as usize .. program.get_buffer().lines[selection.0.1 as usize].len()).rev() {
-                program.get_buffer().lines[selection.0.1 as usize].remove(i);
-              }
+            for i in (selection.0.0 as usize .. program.get_buffer().lines[selection.0.1 as usize].len()).rev() {
+              program.get_buffer().lines[selection.0.1 as usize].remove(i);
+            }
 
 
-              let mut removed = 0;
-              for i in (( selection.0.1+1 ) as usize .. ( selection.1.1) as usize).rev() {
-                program.get_buffer().lines.remove(i);
-                removed+=1;
-              }
+            let mut removed = 0;
+            for i in (( selection.0.1+1 ) as usize .. ( selection.1.1) as usize).rev() {
+              program.get_buffer().lines.remove(i);
+              removed+=1;
+            }
 
 
+            
+            for i in (0 .. selection.1.0 as usize-removed+1).rev() {
+              //println!("{}", i, );
+              program.get_buffer().lines[selection.1.1 as usize - removed].remove(i);
               
-              for i in (0 .. selection.1.0 as usize-removed+1).rev() {
-                //println!("{}", i, );
-                program.get_buffer().lines[selection.1.1 as usize - removed].remove(i);
-                
-              }
-              let lastline = &program.get_buffer().lines[selection.1.1 as usize - removed].clone();
-              program.get_buffer().lines[selection.0.1 as usize] += lastline;
-              program.get_buffer().lines.remove(selection.1.1 as usize -removed);
-              program.get_buffer().cursor = selection.0;
             }
-            program.state = State::Control;
-          },
-        }
+            let lastline = &program.get_buffer().lines[selection.1.1 as usize - removed].clone();
+            program.get_buffer().lines[selection.0.1 as usize] += lastline;
+            program.get_buffer().lines.remove(selection.1.1 as usize -removed);
+            program.get_buffer().cursor = selection.0;
           }
-          _ => {},
+          program.state = State::Control;
+        },
+      }
         }
-      },
-      KeyCode::Backspace => {
-        match program.get_buffer().buf_type.clone() {
-          BufferType::File => {
+        _ => {},
+      }
+    },
+    KeyCode::Backspace => {
+      match program.get_buffer().buf_type.clone() {
+        BufferType::File => {
 
 
-        match program.state {
-          State::Command => {
-            if program.io_cursor > 1 {
-              let mut ioc = program.io.chars().collect::<Vec<char>>();
-              ioc.remove(program.io_cursor as usize -1);
-              program.io = ioc.into_iter().collect::<String>();
-              program.move_io_cursor(-1);
-              if program.io.len()==0 { // not needed (?)
-                program.state = State::Control;
-              }
-            } else {
+      match program.state {
+        State::Command => {
+          if program.io_cursor > 1 {
+            let mut ioc = program.io.chars().collect::<Vec<char>>();
+            ioc.remove(program.io_cursor as usize -1);
+            program.io = ioc.into_iter().collect::<String>();
+            program.move_io_cursor(-1);
+            if program.io.len()==0 { // not needed (?)
               program.state = State::Control;
             }
-          },
-          State::Input => {
-            if program.get_buffer().cursor.0>0 {
-              let index = (program.get_buffer().cursor.1) as usize;
-              let x = program.get_buffer().cursor.0 as usize;
-              let temp = &program.get_buffer().lines[index].chars().collect::<Vec<char>>()[..x];
-              let iter = temp.into_iter().collect::<String>();
-              let mut strc = iter.chars();
-              strc.next_back();
-              program.get_buffer().lines[index] = strc.collect::<String>() + &program.get_buffer().lines[index].chars().collect::<Vec<char>>()[x..].into_iter().collect::<String>();
-            
-              program.move_cursor((-1,0));
-            } else if program.get_buffer().cursor.0 == 0 && program.get_buffer().cursor.1 > 0 {
-              let cursor = program.get_buffer().cursor.1;
-              let cline = program.get_buffer().lines[cursor as usize].clone();
-              program.get_buffer().lines[cursor as usize -1] += &cline;
-              program.get_buffer().lines.remove(cursor as usize);
-              let x = (program.get_buffer().lines[cursor as usize -1].len() - cline.len()) as i32;
-              program.move_cursor((x, -1));
-              if program.get_buffer().cursor.1 == program.get_buffer().lines.len() as u32 {
-                program.get_buffer().display_start_line -= 1;
-              }
-            }
-          },
-          State::Control => {
-            if program.get_buffer().cursor.0 == 0 && program.get_buffer().cursor.1 > 0 {
-              program.move_cursor((i16::MAX as i32, -1));
-            } else {
-              program.move_cursor((-1,0));
+          } else {
+            program.state = State::Control;
+          }
+        },
+        State::Input => {
+          if program.get_buffer().cursor.0>0 {
+            let index = (program.get_buffer().cursor.1) as usize;
+            let x = program.get_buffer().cursor.0 as usize;
+            let temp = &program.get_buffer().lines[index].chars().collect::<Vec<char>>()[..x];
+            let iter = temp.into_iter().collect::<String>();
+            let mut strc = iter.chars();
+            strc.next_back();
+            program.get_buffer().lines[index] = strc.collect::<String>() + &program.get_buffer().lines[index].chars().collect::<Vec<char>>()[x..].into_iter().collect::<String>();
+          
+            program.move_cursor((-1,0));
+          } else if program.get_buffer().cursor.0 == 0 && program.get_buffer().cursor.1 > 0 {
+            let cursor = program.get_buffer().cursor.1;
+            let cline = program.get_buffer().lines[cursor as usize].clone();
+            program.get_buffer().lines[cursor as usize -1] += &cline;
+            program.get_buffer().lines.remove(cursor as usize);
+            let x = (program.get_buffer().lines[cursor as usize -1].len() - cline.len()) as i32;
+            program.move_cursor((x, -1));
+            if program.get_buffer().cursor.1 == program.get_buffer().lines.len() as u32 {
+              program.get_buffer().display_start_line -= 1;
             }
-          },
-          State::Selection => {
+          }
+        },
+        State::Control => {
+          if program.get_buffer().cursor.0 == 0 && program.get_buffer().cursor.1 > 0 {
+            program.move_cursor((i16::MAX as i32, -1));
+          } else {
             program.move_cursor((-1,0));
-            program.move_selection((-1,0));
-          },
-        }
           }
-          BufferType::Directory(_) => {
-            match program.state {
-              State::Command => {
-                if program.io_cursor > 1 {
-              let mut ioc = program.io.chars().collect::<Vec<char>>();
-              ioc.remove(program.io_cursor as usize -1);
-              program.io = ioc.into_iter().collect::<String>();
-              program.move_io_cursor(-1);
-              if program.io.len()==0 { // not needed (?)
-                program.state = State::Control;
-              }
-            } else {
-              program.state = State::Control;
-            }
-              },
-              _ => {}
-            }
-          },
-          _ => {},
+        },
+        State::Selection => {
+          program.move_cursor((-1,0));
+          program.move_selection((-1,0));
+        },
+      }
         }
-      },
-      KeyCode::Colon => {
-        match program.state {
-          State::Command => {
-            program.io += ":";
-            program.move_io_cursor(1);
-          
-          },
-          State::Input => {
-            match program.get_buffer().buf_type.clone() {
-              BufferType::File => {
-                program.write_string(String::from(":"));
-                program.move_cursor((1,0));
-              },
-              _ => {},
+        BufferType::Directory(_) => {
+          match program.state {
+            State::Command => {
+              if program.io_cursor > 1 {
+            let mut ioc = program.io.chars().collect::<Vec<char>>();
+            ioc.remove(program.io_cursor as usize -1);
+            program.io = ioc.into_iter().collect::<String>();
+            program.move_io_cursor(-1);
+            if program.io.len()==0 { // not needed (?)
+              program.state = State::Control;
             }
-          },
-          State::Control => {
-            program.state = State::Command;
-            program.io = String::from(":");
-            program.io_cursor = 1;
-          },
-          State::Selection => {
-            program.state = State::Command;
-            program.io = String::from(":");
-            program.io_cursor = 1;
-          },
-        }
+          } else {
+            program.state = State::Control;
+          }
+            },
+            _ => {}
+          }
+        },
+        _ => {},
+      }
+    },
+    KeyCode::Colon => {
+      match program.state {
+        State::Command => {
+          program.io += ":";
+          program.move_io_cursor(1);
+        
+        },
+        State::Input => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File => {
+              program.write_string(String::from(":"));
+              program.move_cursor((1,0));
+            },
+            _ => {},
+          }
+        },
+        State::Control => {
+          program.state = State::Command;
+          program.io = String::from(":");
+          program.io_cursor = 1;
+        },
+        State::Selection => {
+          program.state = State::Command;
+          program.io = String::from(":");
+          program.io_cursor = 1;
+        },
+      }
 
-      },
-      KeyCode::Arrow(d) => {
-        match d {
-          Direction::Up => {
-            match program.state {
-              State::Command => {
-                if program.io_history_index < program.io_history.len() {
-                  program.io_history_index += 1;
-                } else {
-                  program.io_history_index = 1;
-                }
-                if program.io_history.len() > 0 {
-                  program.io = program.io_history[program.io_history_index-1].clone();
-                  program.io_cursor = program.io_history[program.io_history_index-1].clone().len() as u32;
-                }
-              },
-              State::Selection => {
-                program.move_cursor((0, -1));
-                program.move_selection((0,-1));
-              },
-              _ => {
-                match program.get_buffer().buf_type.clone() {
-                  BufferType::File => {
-                    program.move_cursor((0, -1));
-                  },
-                  BufferType::Directory(d) => {
-                    let mut d = d;
-                    d.subdirs[d.selected_index].selected = false;
-                    if d.selected_index as i32 -1 < 0 {
-                      d.selected_index = d.subdirs.len()-1;
-                      program.move_cursor((0,i32::MAX));
-                      program.move_cursor((0,-1));
-                    } else {
-                      d.selected_index -= 1;
-                      program.move_cursor((0,-1));
-                    }
-                    d.subdirs[d.selected_index].selected = true;
-                    program.get_buffer().lines = d.clone().getlines();
-                    program.get_buffer().buf_type = BufferType::Directory(d);
-                  },
-                  _ => {},
-                }
-              }
-            }
-          },
-          Direction::Down => {
-            match program.state {
-              State::Command => {
-                if program.io_history.len()>0 {
-                  if program.io_history_index > 0 { // what
-                    program.io = program.io_history[program.io_history_index-1].clone();
-                    program.io_cursor = program.io_history[program.io_history_index-1].clone().len() as u32;
-                    program.io_history_index -= 1;
+    },
+    KeyCode::Arrow(d) => {
+      match d {
+        Direction::Up => {
+          match program.state {
+            State::Command => {
+              history::browse(program, true);
+            },
+            State::Selection => {
+              program.move_cursor((0, -1));
+              program.move_selection((0,-1));
+            },
+            _ => {
+              match program.get_buffer().buf_type.clone() {
+                BufferType::File => {
+                  program.move_cursor((0, -1));
+                },
+                BufferType::Directory(d) => {
+                  let mut d = d;
+                  d.subdirs[d.selected_index].selected = false;
+                  if d.selected_index as i32 -1 < 0 {
+                    d.selected_index = d.subdirs.len()-1;
+                    program.move_cursor((0,i32::MAX));
+                    program.move_cursor((0,-1));
                   } else {
-                    program.io = String::from(":");
-                    program.io_cursor = 1;
+                    d.selected_index -= 1;
+                    program.move_cursor((0,-1));
                   }
-                }
-              }
-              State::Selection => {
-                program.move_cursor((0, 1));
-                program.move_selection((0,1));
-              },
-              _ => {
-                 match program.get_buffer().buf_type.clone() {
-                  BufferType::File => {
-                    program.move_cursor((0, 1));
-                  },
-                  BufferType::Directory(d) => {
-                    let mut d = d;
-                    d.subdirs[d.selected_index].selected = false;
-                    if d.selected_index as i32 +1 == d.subdirs.len() as i32 {
-                      d.selected_index = 0;
-                      program.move_cursor((0,-i32::MAX));
-                    } else {
-                      d.selected_index += 1;
-                      program.move_cursor((0,1));
-                    }
-
-                    d.subdirs[d.selected_index].selected = true;
-                    program.get_buffer().lines = d.clone().getlines();
-                    program.get_buffer().buf_type = BufferType::Directory(d);
-                  },
-                  _ => {},
-                }
+                  d.subdirs[d.selected_index].selected = true;
+                  program.get_buffer().lines = d.clone().getlines();
+                  program.get_buffer().buf_type = BufferType::Directory(d);
+                },
+                _ => {},
               }
             }
-          },
-          Direction::Left => {
-            match program.state {
-              State::Command => {
-                program.move_io_cursor(-1);
-              },
-              State::Selection => {
-                program.move_cursor((-1, 0));
-                program.move_selection((-1, 0));
-              },
-              _ => {
-                match program.get_buffer().buf_type.clone() {
-                  BufferType::File => {
-                    program.move_cursor((-1, 0));
-                  },
-                  _ => {},
-                }
-              }
+          }
+        },
+        Direction::Down => {
+          match program.state {
+            State::Command => {
+              history::browse(program, false);
             }
-          },
-          Direction::Right => {
-            match program.state {
-              State::Command => {
-                program.move_io_cursor(1);
-              },
-              State::Selection => {
-                program.move_cursor((1, 0));
-                program.move_selection((1, 0));
-              },
-              _ => {
-                match program.get_buffer().buf_type.clone() {
-                  BufferType::File => {
-                    program.move_cursor((1, 0));
-                  },
-                  BufferType::Directory(d) => {
-                    program.open(d.subdirs[d.selected_index].abs_path.clone());
-                    program.close(program.current);
-                    program.current = program.buffers.len()-1;
-                    program.state = State::Control;
-                  },
-                  _ => {},
-                }
+            State::Selection => {
+              program.move_cursor((0, 1));
+              program.move_selection((0,1));
+            },
+            _ => {
+               match program.get_buffer().buf_type.clone() {
+                BufferType::File => {
+                  program.move_cursor((0, 1));
+                },
+                BufferType::Directory(d) => {
+                  let mut d = d;
+                  d.subdirs[d.selected_index].selected = false;
+                  if d.selected_index as i32 +1 == d.subdirs.len() as i32 {
+                    d.selected_index = 0;
+                    program.move_cursor((0,-i32::MAX));
+                  } else {
+                    d.selected_index += 1;
+                    program.move_cursor((0,1));
+                  }
+
+                  d.subdirs[d.selected_index].selected = true;
+                  program.get_buffer().lines = d.clone().getlines();
+                  program.get_buffer().buf_type = BufferType::Directory(d);
+                },
+                _ => {},
               }
             }
-          },
-        }
-      },
-      KeyCode::Tab => {
-        match program.state {
-          State::Command => {
-            let left = (program.io[0..program.io_cursor as usize]).to_owned() + &vec![' '; program.config.ops.tab_size].into_iter().collect::<String>();
-            program.io = left + &program.io[program.io_cursor as usize..];
-            program.move_io_cursor(1);
-          },
-          State::Input => {
-            match program.get_buffer().buf_type.clone() {
-              BufferType::File => {
-                program.write_string(vec![' '; program.config.ops.tab_size].into_iter().collect::<String>());
-                program.move_cursor((program.config.ops.tab_size as i32,0));
+          }
+        },
+        Direction::Left => {
+          match program.state {
+            State::Command => {
+              program.move_io_cursor(-1);
+            },
+            State::Selection => {
+              program.move_cursor((-1, 0));
+              program.move_selection((-1, 0));
+            },
+            _ => {
+              match program.get_buffer().buf_type.clone() {
+                BufferType::File => {
+                  program.move_cursor((-1, 0));
+                },
+                _ => {},
               }
-              _ => {}
             }
-          },
-          State::Selection => {
-            let mut selection = program.get_buffer().selection;
-
-            if selection.1.1 < selection.0.1 {
-              let s0 = selection.0;
-              selection.0.1 = selection.1.1;
-              selection.0.0 = selection.1.0;
-              selection.1.1 = s0.1;
-              selection.1.0 = s0.0;
+          }
+        },
+        Direction::Right => {
+          match program.state {
+            State::Command => {
+              program.move_io_cursor(1);
+            },
+            State::Selection => {
+              program.move_cursor((1, 0));
+              program.move_selection((1, 0));
+            },
+            _ => {
+              match program.get_buffer().buf_type.clone() {
+                BufferType::File => {
+                  program.move_cursor((1, 0));
+                },
+                BufferType::Directory(_) => {
+                  explorer::open_selected(program);
+                },
+                _ => {},
+              }
             }
-            if selection.0.1 == selection.1.1 && selection.0.0 > selection.1.0 {
-              let s10 = selection.1.0;
-              selection.1.0 = selection.0.0;
-              selection.0.0 = s10;
+          }
+        },
+      }
+    },
+    KeyCode::Tab if program.state == State::Selection && !program.editable() => {},
+    KeyCode::Tab => {
+      match program.state {
+        State::Command => {
+          completion::complete(program, !event.modifiers.contains(&Modifier::Shift));
+        },
+        State::Input => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File => {
+              program.write_string(vec![' '; program.config.ops.tab_size].into_iter().collect::<String>());
+              program.move_cursor((program.config.ops.tab_size as i32,0));
             }
+            _ => {}
+          }
+        },
+        State::Selection => {
+          let mut selection = program.get_buffer().selection;
+
+          if selection.1.1 < selection.0.1 {
+            let s0 = selection.0;
+            selection.0.1 = selection.1.1;
+            selection.0.0 = selection.1.0;
+            selection.1.1 = s0.1;
+            selection.1.0 = s0.0;
+          }
+          if selection.0.1 == selection.1.1 && selection.0.0 > selection.1.0 {
+            let s10 = selection.1.0;
+            selection.1.0 = selection.0.0;
+            selection.0.0 = s10;
+          }
 
-            let tab = &vec![' '; program.config.ops.tab_size].into_iter().collect::<String>();
-            for i in selection.0.1..selection.1.1+1 {
-              let line = &program.get_buffer().lines[i as usize];
-              program.get_buffer().lines[i as usize] = tab.to_string() + line;
-            }
-            program.move_selection((4,0));
-          },
-          _ => {}
-        }
-      },
-      KeyCode::Char(c) => {
-        match program.state { 
-          State::Command => {
-            let left = (program.io[0..program.io_cursor as usize]).to_owned() + &c.to_string();
-            program.io = left + &program.io[program.io_cursor as usize..];
-            program.move_io_cursor(1);
-          },
-          State::Control => {
-            match c {
-              'i' => {program.state = State::Input;},
-              'a' => {program.state = State::Input;},
-              _ => {
-                //program.io = String::from("You're in Control Mode!");
-              },
-            }
-          },
-          State::Input => {
-            match program.get_buffer().buf_type.clone() {
-              BufferType::File => {
-                program.write_string(c.to_string());
-                program.move_cursor((1,0));
+          let tab = &vec![' '; program.config.ops.tab_size].into_iter().collect::<String>();
+          for i in selection.0.1..selection.1.1+1 {
+            let line = &program.get_buffer().lines[i as usize];
+            program.get_buffer().lines[i as usize] = tab.to_string() + line;
+          }
+          program.move_selection((4,0));
+        },
+        _ => {}
+      }
+    },
+    KeyCode::Char('r') if program.state == State::Command && event.modifiers == vec![Modifier::Control] => {
+      history::start_search(program);
+    },
+    KeyCode::Char(_) if event.modifiers.iter().any(|m| *m != Modifier::Shift) => {}, // unbound ctrl/alt combos don't type anything
+    KeyCode::Char(c) => {
+      match program.state { 
+        State::Command => {
+          let left = (program.io[0..program.io_cursor as usize]).to_owned() + &c.to_string();
+          program.io = left + &program.io[program.io_cursor as usize..];
+          program.move_io_cursor(1);
+        },
+        State::Control => {
+          match c {
+            'i' | 'a' if program.editable() => {program.state = State::Input;},
+            '.' => {
+              if let BufferType::Directory(d) = program.get_buffer().buf_type.clone() {
+                explorer::refresh(program, !d.show_hidden);
               }
-              _ => {}
+            },
+            _ => {
+              //program.io = String::from("You're in Control Mode!");
+            },
+          }
+        },
+        State::Input => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File => {
+              program.write_string(c.to_string());
+              program.move_cursor((1,0));
             }
-          },
-          State::Selection => {},
+            _ => {}
+          }
+        },
+        State::Selection => {},
+      }
+    },
+    KeyCode::Home => {
+      match program.state {
+        State::Command => {
+          program.io_cursor = 1;
+        },
+        State::Selection => {
+          let x = program.get_buffer().cursor.0 as i32;
+          program.move_cursor((-x, 0));
+          program.move_selection((-x, 0));
+        },
+        _ => {
+          program.move_cursor((-i32::MAX, 0));
         }
-      },
-    }
+      }
+    },
+    KeyCode::End => {
+      match program.state {
+        State::Command => {
+          program.io_cursor = program.io.len() as u32;
+        },
+        State::Selection => {
+          program.move_cursor((i16::MAX as i32, 0));
+          program.move_selection((i16::MAX as i32, 0));
+        },
+        _ => {
+          program.move_cursor((i16::MAX as i32, 0));
+        }
+      }
+    },
+    KeyCode::PageUp | KeyCode::PageDown => {
+      let page = (tery as i32 - 3) * if event.code == KeyCode::PageUp {-1} else {1};
+      match program.state {
+        State::Command => {},
+        State::Selection => {
+          program.move_cursor((0, page));
+          program.move_selection((0, page));
+        },
+        _ => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File => {
+              program.move_cursor((0, page));
+            },
+            _ => {}
+          }
+        }
+      }
+    },
+    KeyCode::Insert => {
+      match program.state.clone() {
+        State::Control if program.editable() => {program.state = State::Input;},
+        State::Input => {program.state = State::Control;},
+        _ => {}
+      }
+    },
+    KeyCode::F(_) => {},
+    KeyCode::Paste(text) => {
+      match program.state {
+        State::Command => {
+          let left = (program.io[0..program.io_cursor as usize]).to_owned() + &text.replace("\n", " ");
+          program.io = left.clone() + &program.io[program.io_cursor as usize..];
+          program.io_cursor = left.len() as u32;
+        },
+        State::Input => {
+          match program.get_buffer().buf_type.clone() {
+            BufferType::File => {
+              program.insert_text(text);
+            }
+            _ => {}
+          }
+        },
+        _ => {}
+      }
+    },
   }
-  program.clone()
 }
 
 
@@ -1632,174 +2412,167 @@ fn main() {
     io_cursor: 0,
     io_history: vec![],
     io_history_index: 0,
+    history_prefix: String::new(),
+    history_search: None,
+    completion: None,
+    pending_keys: vec![],
+    tab_offset: 0,
+    swaps: HashMap::new(),
+    last_swap: std::time::Instant::now(),
+    recovery: vec![],
+    confirm: None,
+    tree: None,
+    finder: None,
+    blame: None,
+    statusline_cache: vec![],
     exit: false,
 
     foklang: foklang::foklang::Foklang::new(),
     config: FokEditConfig{..Default::default()},
   };
-  let mut args = env::args();
-  args.next();
-  for i in args {
-    program.open(i);
-  }
-  if program.buffers.len() == 0 {
-    program.buffers.push(
-      EditorBuffer {
-        cursor:  (0, 0),
-        selection: ((0,0), (0,0)),
-        lines: vec![String::new()],
-        old_lines: vec![String::new()],
-        buf_type: BufferType::File,
-        display_start_line: 0,
-        display_offset_collumn: 0,
-        buf_name: String::from("unnamed"),
-        save_path: String::from(""),
-        saved: false,
+  let mut args = env::args().skip(1).collect::<Vec<String>>();
+  let view = args.contains(&String::from("-R")); // open everything read-only
+  args.retain(|x| x != "-R");
+  let no_files = args.is_empty();
+  // the config decides how files open (large files, swaps) so it goes first, with a buffer for it to look at
+  program.buffers.push(EditorBuffer {
+    cursor:  (0, 0),
+    selection: ((0,0), (0,0)),
+    lines: vec![String::new()],
+    old_lines: vec![String::new()],
+    buf_type: BufferType::File,
+    display_start_line: 0,
+    display_offset_collumn: 0,
+    buf_name: String::from("unnamed"),
+    save_path: String::from(""),
+    saved: false,
+    format: Default::default(),
+    saved_format: Default::default(),
+    large: None,
+    disk: None,
+    read_only: false,
+    changes: None,
+    git: None,
+  });
+  program.reload();
+  if !no_files {
+    for i in args {
+      let count = program.buffers.len();
+      program.open(i);
+      if view && program.buffers.len() > count {
+        program.buffers.last_mut().unwrap().read_only = true;
       }
-    );
+    }
+    if program.buffers.len() > 1 {
+      program.close(0);
+    }
   }
+  program.io_history = history::load();
 
-
-
-  program.reload();
-
+  if no_files && program.config.ops.auto_session && Path::new(&session::session_path()).exists() {
+    if let Err(e) = session::load(&mut program, &session::session_path()) {
+      program.io = e;
+    }
+  }
 
 
 
-  print!("\x1b 7\x1b[?47h");
+  print!("\x1b 7\x1b[?47h\x1b[?2004h"); // alternate screen + bracketed paste
+  if program.config.ops.kitty_keyboard {
+    print!("\x1b[>1u"); // kitty keyboard protocol: disambiguate escape codes
+  }
   /// MAIN_LOOP 
 
   program.display();
-  for b in io::stdin().bytes() {
+  loop {
     
     //println!("{:#?}", (*program.lock().unwrap()).state);
     
-    let c = b.unwrap() as char;
-    //println!("{}", c);
-    let mut modifiers: Vec<Modifier> = vec![];
-    if c.is_control() && ![ENTER, TAB, ESCAPE, BACKSPACE].contains(&c) {
-      modifiers.push(Modifier::Control);
-    }
-    
-    let event = KeyEvent{
-      code: match c { BACKSPACE => KeyCode::Backspace, ':' => KeyCode::Colon, '\n' => KeyCode::Enter,
-          '\t' => KeyCode::Tab,
-          'Ã' => {
-            match getch() {
-              '³' => {
-                KeyCode::Char('ó')
-              },
-              '\u{93}' => {
-                KeyCode::Char('Ó')
-              },
-              _ => KeyCode::Escape
-            }
-          },
-          'Ä' => {
-            match getch() {
-              '\u{99}' => {
-                KeyCode::Char('ę')
-              },
-              '\u{98}' => {
-                KeyCode::Char('Ę')
-              },
-              '\u{87}' => {
-                KeyCode::Char('ć')
-              },
-              '\u{86}' => {
-                KeyCode::Char('Ć')
-              },
-
-              '\u{85}' => {
-                KeyCode::Char('ą')
-              },
-              '\u{84}' => {
-                KeyCode::Char('Ą')
-              },
-              _ => KeyCode::Escape
-            }
-          },
-          'Å' => {
-            match getch() {
-              '\u{84}' => {
-                KeyCode::Char('ń')
-              },
-              '\u{83}' => {
-                KeyCode::Char('Ń')
-              },
-              '\u{82}' => {
-                KeyCode::Char('ł')
-              },
-              '\u{81}' => {
-                KeyCode::Char('Ł')
-              },
-              '\u{9b}' => {
-                KeyCode::Char('ś')
-              },
-              '\u{9a}' => {
-                KeyCode::Char('Ś')
-              },
-              'º' => {
-                KeyCode::Char('ź')
-              },
-              '¹' => {
-                KeyCode::Char('Ź')
-              },
-              '¼' => {
-                KeyCode::Char('ż')
-              },
-              '»' => {
-                KeyCode::Char('Ż')
-              }
-              _ => KeyCode::Escape
-            }
-          },
-          '\u{1b}' => {
-              match getch() { 
-                    '[' => match getch() {
-                        'A' => KeyCode::Arrow(Direction::Up), 'B' => KeyCode::Arrow(Direction::Down), 'C' => KeyCode::Arrow(Direction::Right), 'D' => KeyCode::Arrow(Direction::Left),
-                        '1' => match getch() {
-                                ';' => match getch() 
-                                { '5' => {modifiers.push(Modifier::Control); get_arrow()}, '2' => {modifiers.push(Modifier::Shift); get_arrow()}, _ => KeyCode::Escape}, _ => KeyCode::Escape
-                            },
-                        '3' => match getch() {
-                              '~' => KeyCode::Delete,
-                              _ => KeyCode::Escape,
-                            },
-                        _ => KeyCode::Escape }, 
-                    _ => KeyCode::Escape}},
-          _ => KeyCode::Char(c)},
-      modifiers,
+    let swap_interval = program.config.ops.swap_interval;
+    let swap_timeout = if swap_interval > 0 && program.buffers.iter().any(|x| x.modified()) {Some(swap_interval as i32)} else {None};
+    let index_timeout = if large::indexing(&program) {Some(250)} else {None}; // keep the line count moving
+    let watch_timeout = if program.buffers.iter().any(|x| x.disk.is_some() || x.git.is_some()) {Some(watch::POLL_INTERVAL)} else {None};
+    let blame_timeout = if git::blame_pending(&mut program) {Some(git::BLAME_DELAY)} else {None};
+    let timeout = if !program.pending_keys.is_empty() {Some(program.config.ops.keybind_timeout as i32)} else {[index_timeout, swap_timeout, watch_timeout, blame_timeout].into_iter().flatten().min()};
+    let event = match input::wait_byte(timeout) {
+      input::Stdin::Byte(byte) => match input::decode(byte) {
+        Some(event) => Some(event),
+        None => continue, // unknown sequence / key release
+      },
+      input::Stdin::Timeout if !program.pending_keys.is_empty() => None, // keybind sequence timed out
+      input::Stdin::Timeout => { // idle, good time to update swap files
+        if swap_timeout.is_some() && program.last_swap.elapsed().as_millis() >= swap_interval as u128 {
+          swap::sync(&mut program, true);
+          program.last_swap = std::time::Instant::now();
+        }
+        let changed = watch::check(&mut program) | git::poll(&mut program) | git::update_blame(&mut program);
+        if index_timeout.is_some() {
+          large::update(&mut program);
+        }
+        if changed || index_timeout.is_some() {
+          program.display();
+        }
+        continue;
+      },
+      input::Stdin::Closed => { // the terminal went away, keep what's unsaved in the swap files
+        swap::sync(&mut program, true);
+        break;
+      },
     };
     //program.io =  format!("{:#?}", event);
     
     
     let panics = std::panic::catch_unwind(|| {
-      handle_key_event(&mut program.clone(), event.clone())
+      match event.clone() {
+        Some(event) => handle_key_event(&mut program.clone(), event),
+        None => flush_pending_keys(&mut program.clone()),
+      }
     });
     
     if panics.is_ok() { /* safety layer */
       program = panics.unwrap().clone();
     } else {
-      program.io = format!("FokEdit panicked trying to handle: {:#?}.", event.code);
+      program.pending_keys.clear();
+      program.io = format!("FokEdit panicked trying to handle: {:#?}.", event.map(|x| x.code));
     }
 
     //handle_key_event(&mut program, event);
     if program.exit || program.buffers.len() == 0 {
+      if program.config.ops.auto_session && !program.buffers.is_empty() {
+        let _ = session::save(&program, &session::session_path());
+      }
+      swap::remove_all(&mut program);
       break;
     }
+    if swap_interval > 0 {
+      let due = program.last_swap.elapsed().as_millis() >= swap_interval as u128;
+      swap::sync(&mut program, due);
+      if due {
+        program.last_swap = std::time::Instant::now();
+      }
+    }
+    watch::check(&mut program);
+    git::poll(&mut program);
+    large::update(&mut program);
+    hex::snap(&mut program);
+    git::invalidate_blame(&mut program);
     let panics = std::panic::catch_unwind(|| {
-      program.clone().display();
+      let mut shown = program.clone();
+      shown.display();
+      shown
     });
     
-    if panics.is_ok() { /* safety layer */
-      panics.unwrap();
+    if let Ok(shown) = panics { /* safety layer */
+      program = shown; // keeps what displaying updated, the statusline cache and scrolling
     } else {
+      swap::sync(&mut program, true);
       program.clear();
       println!("\x1b[38;2;255;0;0mError: Failed to display contents - perhaps unicode issues?\x1b[0m");
-      panic!("panicked due to above reason")
+      if !program.swaps.is_empty() {
+        println!("Unsaved changes were kept in {}, reopen the files to recover them.", swap::swap_dir());
+      }
+      std::process::exit(1);
     }
-    program.display();
   }
   program.clear(); // clear exit
 
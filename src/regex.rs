@@ -0,0 +1,330 @@
+/* small backtracking regex - enough for searching: literals, `.`, `[a-z]`/`[^...]` classes, `\d \w \s` (and upper case negations),
+   `^ $ \b`, groups with `|`, and the greedy quantifiers `* + ? {n} {n,} {n,m}`. backtracking is bounded in steps
+   and depth, patterns like `(a*)*b` give up on a line instead of taking forever or overflowing the stack */
+use std::cell::Cell;
+
+const MAX_STEPS: usize = 1_000_000; // per find
+const MAX_DEPTH: usize = 10_000;     // nested calls, each repetition or matched char takes a few
+
+#[derive(Debug,Clone,PartialEq)]
+enum Node {
+  Char(char),
+  Any,
+  Class(Vec<(char, char)>, bool), // ranges, negated
+  Start,
+  End,
+  WordBoundary,
+  Group(Vec<Vec<Node>>),          // alternatives
+  Repeat(Box<Node>, usize, usize),
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Regex {
+  nodes: Vec<Node>, // the pattern as one group
+  ignore_case: bool,
+  steps: Cell<usize>,
+  depth: Cell<usize>,
+}
+
+fn escape_class(c: char) -> Option<(Vec<(char, char)>, bool)> {
+  let word = vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+  let space = vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r'), ('\x0b', '\x0c')];
+  match c {
+    'd' => Some((vec![('0', '9')], false)),
+    'D' => Some((vec![('0', '9')], true)),
+    'w' => Some((word, false)),
+    'W' => Some((word, true)),
+    's' => Some((space, false)),
+    'S' => Some((space, true)),
+    _ => None,
+  }
+}
+
+struct Parser {
+  chars: Vec<char>,
+  pos: usize,
+}
+
+impl Parser {
+  fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+    let mut alternatives = vec![self.sequence()?];
+    while self.chars.get(self.pos) == Some(&'|') {
+      self.pos += 1;
+      alternatives.push(self.sequence()?);
+    }
+    Ok(alternatives)
+  }
+
+  fn sequence(&mut self) -> Result<Vec<Node>, String> {
+    let mut nodes = vec![];
+    while let Some(&c) = self.chars.get(self.pos) {
+      if c == '|' || c == ')' {
+        break;
+      }
+      self.pos += 1;
+      let node = match c {
+        '.' => Node::Any,
+        '^' => Node::Start,
+        '$' => Node::End,
+        '(' => {
+          if self.chars[self.pos..].starts_with(&['?', ':']) {
+            self.pos += 2;
+          }
+          let group = self.alternatives()?;
+          if self.chars.get(self.pos) != Some(&')') {
+            return Err(String::from("missing )"));
+          }
+          self.pos += 1;
+          Node::Group(group)
+        },
+        '[' => self.class()?,
+        '\\' => {
+          let e = *self.chars.get(self.pos).ok_or("trailing \\")?;
+          self.pos += 1;
+          match e {
+            'b' => Node::WordBoundary,
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            _ => match escape_class(e) {
+              Some((ranges, negated)) => Node::Class(ranges, negated),
+              None => Node::Char(e),
+            }
+          }
+        },
+        '*' | '+' | '?' => return Err(format!("nothing to repeat before {}", c)),
+        _ => Node::Char(c),
+      };
+      nodes.push(self.quantifier(node)?);
+    }
+    Ok(nodes)
+  }
+
+  fn quantifier(&mut self, node: Node) -> Result<Node, String> {
+    let (min, max) = match self.chars.get(self.pos) {
+      Some('*') => (0, usize::MAX),
+      Some('+') => (1, usize::MAX),
+      Some('?') => (0, 1),
+      Some('{') => {
+        let end = match self.chars[self.pos..].iter().position(|c| *c == '}') {
+          Some(e) => self.pos + e,
+          None => return Ok(node), // a literal `{`
+        };
+        let inside = self.chars[self.pos+1..end].iter().collect::<String>();
+        let (min, max) = match inside.split_once(',') {
+          Some((min, "")) => (min.parse::<usize>(), Ok(usize::MAX)),
+          Some((min, max)) => (min.parse::<usize>(), max.parse::<usize>()),
+          None => (inside.parse::<usize>(), inside.parse::<usize>()),
+        };
+        match (min, max) {
+          (Ok(min), Ok(max)) if min <= max => {
+            self.pos = end;
+            (min, max)
+          },
+          _ => return Ok(node),
+        }
+      },
+      _ => return Ok(node),
+    };
+    self.pos += 1;
+    if matches!(node, Node::Start | Node::End | Node::WordBoundary) {
+      return Err(String::from("can't repeat an anchor"));
+    }
+    Ok(Node::Repeat(Box::new(node), min, max))
+  }
+
+  fn class(&mut self) -> Result<Node, String> {
+    let negated = self.chars.get(self.pos) == Some(&'^');
+    if negated {
+      self.pos += 1;
+    }
+    let mut ranges = vec![];
+    let mut first = true;
+    loop {
+      let c = *self.chars.get(self.pos).ok_or("missing ]")?;
+      self.pos += 1;
+      if c == ']' && !first {
+        break;
+      }
+      first = false;
+      let c = if c == '\\' {
+        let e = *self.chars.get(self.pos).ok_or("missing ]")?;
+        self.pos += 1;
+        if let Some((mut class, false)) = escape_class(e) {
+          ranges.append(&mut class);
+          continue;
+        }
+        match e {'n' => '\n', 't' => '\t', _ => e}
+      } else {c};
+      if self.chars.get(self.pos) == Some(&'-') && self.chars.get(self.pos+1).map(|x| *x != ']').unwrap_or(false) {
+        let end = self.chars[self.pos+1];
+        self.pos += 2;
+        ranges.push((c, end));
+      } else {
+        ranges.push((c, c));
+      }
+    }
+    Ok(Node::Class(ranges, negated))
+  }
+}
+
+fn is_word(c: Option<&char>) -> bool {
+  c.map(|c| c.is_alphanumeric() || *c == '_').unwrap_or(false)
+}
+
+impl Regex {
+  pub fn new(pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+    let mut parser = Parser {chars: pattern.chars().collect(), pos: 0};
+    let alternatives = parser.alternatives()?;
+    if parser.pos < parser.chars.len() {
+      return Err(String::from("unmatched )"));
+    }
+    Ok(Regex {nodes: vec![Node::Group(alternatives)], ignore_case, steps: Cell::new(0), depth: Cell::new(0)})
+  }
+
+  fn single(&self, node: &Node, c: char) -> bool {
+    let eq = |a: char, b: char| a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()));
+    match node {
+      Node::Char(x) => eq(*x, c),
+      Node::Any => c != '\n',
+      Node::Class(ranges, negated) => {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        let upper = c.to_uppercase().next().unwrap_or(c);
+        let inside = ranges.iter().any(|(a, b)| (*a <= c && c <= *b) || (self.ignore_case && ((*a <= lower && lower <= *b) || (*a <= upper && upper <= *b))));
+        inside != *negated
+      },
+      _ => false,
+    }
+  }
+
+  /* match `nodes[index..]` at `pos`, then whatever `next` wants with the position reached */
+  fn matches(&self, nodes: &[Node], index: usize, text: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+    if self.steps.get() >= MAX_STEPS || self.depth.get() >= MAX_DEPTH {
+      self.steps.set(MAX_STEPS); // out of budget, everything fails from here on
+      return false;
+    }
+    self.steps.set(self.steps.get() + 1);
+    self.depth.set(self.depth.get() + 1);
+    let matched = self.step(nodes, index, text, pos, next);
+    self.depth.set(self.depth.get() - 1);
+    matched
+  }
+
+  fn step(&self, nodes: &[Node], index: usize, text: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+    let node = match nodes.get(index) {
+      Some(n) => n,
+      None => return next(pos),
+    };
+    match node {
+      Node::Start => pos == 0 && self.matches(nodes, index+1, text, pos, next),
+      Node::End => pos == text.len() && self.matches(nodes, index+1, text, pos, next),
+      Node::WordBoundary => {
+        (is_word(pos.checked_sub(1).and_then(|p| text.get(p))) != is_word(text.get(pos))) && self.matches(nodes, index+1, text, pos, next)
+      },
+      Node::Group(alternatives) => alternatives.iter().any(|alternative| {
+        self.matches(alternative, 0, text, pos, &mut |p| self.matches(nodes, index+1, text, p, next))
+      }),
+      Node::Repeat(inner, min, max) => self.repeat(inner, *min, *max, 0, nodes, index, text, pos, next),
+      _ => pos < text.len() && self.single(node, text[pos]) && self.matches(nodes, index+1, text, pos+1, next),
+    }
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn repeat(&self, inner: &Node, min: usize, max: usize, count: usize, nodes: &[Node], index: usize, text: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+    // greedy: one more repetition first (empty ones would loop forever), then the rest of the pattern
+    if count < max && self.matches(std::slice::from_ref(inner), 0, text, pos, &mut |p| p != pos && self.repeat(inner, min, max, count+1, nodes, index, text, p, next)) {
+      return true;
+    }
+    count >= min && self.matches(nodes, index+1, text, pos, next)
+  }
+
+  /* first match in `text` as (start, end) char indices, an error when backtracking ran out of budget */
+  pub fn find(&self, text: &[char]) -> Result<Option<(usize, usize)>, String> {
+    self.steps.set(0);
+    self.depth.set(0);
+    for start in 0..=text.len() {
+      let mut end = 0;
+      if self.matches(&self.nodes, 0, text, start, &mut |p| {end = p; true}) {
+        return Ok(Some((start, end)));
+      }
+      if self.steps.get() >= MAX_STEPS {
+        return Err(String::from("the pattern backtracks too much"));
+      }
+    }
+    Ok(None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn find(pattern: &str, text: &str) -> Option<(usize, usize)> {
+    Regex::new(pattern, false).unwrap().find(&text.chars().collect::<Vec<char>>()).unwrap()
+  }
+
+  #[test]
+  fn literals() {
+    assert_eq!(find("bc", "abcd"), Some((1, 3)));
+    assert_eq!(find("a.c", "xabc"), Some((1, 4)));
+    assert_eq!(find("x", "abc"), None);
+    assert_eq!(find("", "abc"), Some((0, 0)));
+    assert_eq!(find("a\\.c", "abc a.c"), Some((4, 7)));
+    let regex = Regex::new("ÓW", true).unwrap();
+    assert_eq!(regex.find(&"zów".chars().collect::<Vec<char>>()), Ok(Some((1, 3))));
+  }
+
+  #[test]
+  fn classes() {
+    assert_eq!(find("[0-9]+", "ab 123 c"), Some((3, 6)));
+    assert_eq!(find("[^a-c ]", "abc d"), Some((4, 5)));
+    assert_eq!(find("\\d\\s\\w", "x1 y"), Some((1, 4)));
+    assert_eq!(find("\\D+", "12ab3"), Some((2, 4)));
+    assert_eq!(find("[a-z]", "ABC"), None);
+  }
+
+  #[test]
+  fn anchors() {
+    assert_eq!(find("^ab", "abab"), Some((0, 2)));
+    assert_eq!(find("^b", "ab"), None);
+    assert_eq!(find("ab$", "abab"), Some((2, 4)));
+    assert_eq!(find("\\bcat\\b", "concat cat"), Some((7, 10)));
+    assert_eq!(find("^$", ""), Some((0, 0)));
+  }
+
+  #[test]
+  fn quantifiers() {
+    assert_eq!(find("ab*", "abbbc"), Some((0, 4)));
+    assert_eq!(find("ab+", "ac abb"), Some((3, 6)));
+    assert_eq!(find("colou?r", "color colour"), Some((0, 5)));
+    assert_eq!(find("a.*b", "a1b2b3"), Some((0, 5))); // greedy
+    assert_eq!(find("(ab|cd)+e", "xabcdabe"), Some((1, 8)));
+    assert_eq!(find("(a|ab)c", "abc"), Some((0, 3)));
+  }
+
+  #[test]
+  fn counted() {
+    assert_eq!(find("a{3}", "aa aaaa"), Some((3, 6)));
+    assert_eq!(find("a{2,}", "a aaaa"), Some((2, 6)));
+    assert_eq!(find("x{1,2}y", "xxxy"), Some((1, 4)));
+    assert_eq!(find("^a{0,1}b", "aab"), None);
+  }
+
+  #[test]
+  fn errors() {
+    assert!(Regex::new("(ab", false).is_err());
+    assert!(Regex::new("ab)", false).is_err());
+    assert!(Regex::new("[ab", false).is_err());
+  }
+
+  #[test]
+  fn backtracking_is_bounded() {
+    // the stack grep searches with, test threads are smaller
+    std::thread::Builder::new().stack_size(16 * 1024 * 1024).spawn(|| {
+      let regex = Regex::new("(a*)*b", false).unwrap();
+      assert!(regex.find(&['a'; 40]).is_err());
+      assert_eq!(regex.find(&['a', 'b']), Ok(Some((0, 2))));
+      let long = Regex::new("a*b", false).unwrap();
+      assert_eq!(long.find(&['a'; 50000]).unwrap_or(None), None); // past the depth budget fails instead of overflowing
+    }).unwrap().join().unwrap();
+  }
+}
@@ -0,0 +1,93 @@
+/* sessions - open buffers, their views and the command history, one file per working directory.
+   format, one entry per line:
+     current <index>
+     buffer <cursor x> <cursor y> <display_start_line> <display_offset_collumn> <path>
+     history <command> */
+use std::{env, fs, path::Path};
+use crate::{BufferType, Editor, Program};
+
+pub fn session_path() -> String {
+  let cwd = env::current_dir().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+  let dir = match env::var("XDG_STATE_HOME") {
+    Ok(dir) if dir != String::new() => dir + "/FokEdit/sessions",
+    _ => env::var("HOME").unwrap() + "/.local/state/FokEdit/sessions",
+  };
+  format!("{}/{}.session", dir, cwd.replace("%", "%%").replace("/", "%"))
+}
+
+pub fn save(program: &Program, path: &str) -> Result<(), String> {
+  let mut lines = vec![];
+  let mut written = 0;
+  for (i, buffer) in program.buffers.iter().enumerate() {
+    if buffer.save_path == String::new() {
+      continue;
+    }
+    if i == program.current {
+      lines.push(format!("current {}", written));
+    }
+    written += 1;
+    let absolute = fs::canonicalize(&buffer.save_path).map(|x| x.to_string_lossy().to_string()).unwrap_or(buffer.save_path.clone());
+    lines.push(format!("buffer {} {} {} {} {}", buffer.cursor.0, buffer.cursor.1, buffer.display_start_line, buffer.display_offset_collumn, absolute));
+  }
+  for command in &program.io_history {
+    lines.push(format!("history {}", command));
+  }
+  if let Some(dir) = Path::new(path).parent() {
+    fs::create_dir_all(dir).map_err(|e| format!("couldn't save session: {}", e))?;
+  }
+  crate::files::save(path, &lines.join("\n"), &crate::files::Backup::None)
+}
+
+/* opens the session's buffers next to the ones already open */
+pub fn load(program: &mut Program, path: &str) -> Result<(), String> {
+  let contents = fs::read_to_string(path).map_err(|e| format!("couldn't load session {}: {}", path, e))?;
+
+  // the empty buffer FokEdit starts with when no files were given
+  let placeholder = program.buffers.len() == 1 && program.buffers[0].save_path == String::new() && !program.buffers[0].modified();
+  let first = program.buffers.len();
+  let mut current = None;
+  let mut opened = vec![]; // buffer index of every `buffer` entry, None if it couldn't be opened
+  let mut history = vec![];
+  for line in contents.split("\n") {
+    let (kind, rest) = line.split_once(" ").unwrap_or((line, ""));
+    match kind {
+      "current" => current = rest.parse::<usize>().ok(),
+      "buffer" => {
+        let fields = rest.splitn(5, " ").collect::<Vec<&str>>();
+        if fields.len() < 5 || !Path::new(fields[4]).exists() {
+          opened.push(None);
+          continue;
+        }
+        let index = program.buffers.len();
+        program.open(fields[4].to_string());
+        if program.buffers.len() == index {
+          opened.push(None); // unreadable
+          continue;
+        }
+        opened.push(Some(index));
+        let numbers = fields[..4].iter().map(|x| x.parse::<u32>().unwrap_or(0)).collect::<Vec<u32>>();
+        let buffer = program.buffers.last_mut().unwrap();
+        if buffer.buf_type == BufferType::File {
+          let y = std::cmp::min(numbers[1] as usize, buffer.lines.len().saturating_sub(1));
+          let x = std::cmp::min(numbers[0] as usize, buffer.lines[y].chars().count());
+          buffer.cursor = (x as u32, y as u32);
+          buffer.display_start_line = std::cmp::min(numbers[2], y as u32);
+          buffer.display_offset_collumn = numbers[3];
+        }
+      },
+      "history" => history.push(rest.to_string()),
+      _ => {}
+    }
+  }
+  program.io_history.retain(|x| !history.contains(x));
+  program.io_history.extend(history);
+  program.io_history_index = 0;
+  if program.buffers.len() == first {
+    return Err(String::from("session has no files left to open"));
+  }
+  program.current = current.and_then(|i| opened.get(i).copied().flatten()).unwrap_or(first);
+  if placeholder {
+    program.close(0);
+  }
+  Ok(())
+}
@@ -0,0 +1,187 @@
+/* swap files - copies of unsaved buffers kept under ~/.local/state/FokEdit/swap so a crash doesn't lose work.
+   a swap file is `pid\n` followed by the buffer text, named after the absolute path of the edited file */
+use std::{collections::hash_map::DefaultHasher, env, fs, hash::{Hash, Hasher}, path::Path};
+use crate::{BufferType, Program, files};
+
+pub fn swap_dir() -> String {
+  match env::var("XDG_STATE_HOME") {
+    Ok(dir) if dir != String::new() => dir + "/FokEdit/swap",
+    _ => env::var("HOME").unwrap() + "/.local/state/FokEdit/swap",
+  }
+}
+
+pub fn swap_path(file: &str) -> String {
+  let absolute = match fs::canonicalize(file) {
+    Ok(p) => p.to_string_lossy().to_string(),
+    Err(_) => file.to_string(),
+  };
+  format!("{}/{}.swp", swap_dir(), absolute.replace("%", "%%").replace("/", "%"))
+}
+
+pub struct Swap {
+  pub pid: i32,
+  pub lines: Vec<String>,
+  pub modified: String, // mtime, for the recovery prompt
+}
+
+pub fn read(file: &str) -> Option<Swap> {
+  let path = swap_path(file);
+  let contents = fs::read_to_string(&path).ok()?;
+  let (pid, text) = contents.split_once("\n")?;
+  let modified = fs::metadata(&path).ok()?.modified().ok()?;
+  let age = modified.elapsed().map(|x| x.as_secs()).unwrap_or(0);
+  Some(Swap {
+    pid: pid.parse::<i32>().unwrap_or(0),
+    lines: text.split("\n").map(|x| x.to_string()).collect(),
+    modified: if age < 60 {format!("{}s ago", age)} else if age < 3600 {format!("{}m ago", age/60)} else {format!("{}h ago", age/3600)},
+  })
+}
+
+/* swap owned by a FokEdit that is still running */
+pub fn in_use(swap: &Swap) -> bool {
+  if swap.pid <= 0 || swap.pid == std::process::id() as i32 || unsafe { libc::kill(swap.pid, 0) } != 0 {
+    return false;
+  }
+  // the pid might have been reused by something else since
+  match (fs::read_to_string(format!("/proc/{}/comm", swap.pid)), fs::read_to_string("/proc/self/comm")) {
+    (Ok(theirs), Ok(ours)) => theirs == ours,
+    _ => true,
+  }
+}
+
+pub fn remove(file: &str) {
+  let _ = fs::remove_file(swap_path(file));
+}
+
+fn hash(lines: &Vec<String>) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  lines.hash(&mut hasher);
+  hasher.finish()
+}
+
+/* drop swaps of buffers saved or closed since; with `write` also swap modified buffers whose text changed since the last sync */
+pub fn sync(program: &mut Program, write: bool) {
+  if !Path::new(&swap_dir()).exists() && fs::create_dir_all(swap_dir()).is_err() {
+    return;
+  }
+  let mut seen = vec![];
+  for buffer in &program.buffers {
+    match buffer.buf_type {
+      BufferType::File => {},
+      _ => continue,
+    }
+    if buffer.save_path == String::new() || program.recovery.contains(&buffer.save_path) {
+      continue;
+    }
+    seen.push(buffer.save_path.clone());
+    if let Some(None) = program.swaps.get(&buffer.save_path) {
+      continue; // another FokEdit's swap
+    }
+    if buffer.modified() {
+      if !write {
+        continue;
+      }
+      let hash = hash(&buffer.lines);
+      if program.swaps.get(&buffer.save_path) != Some(&Some(hash)) {
+        let text = format!("{}\n{}", std::process::id(), buffer.lines.join("\n"));
+        if files::save(&swap_path(&buffer.save_path), &text, &files::Backup::None).is_ok() {
+          program.swaps.insert(buffer.save_path.clone(), Some(hash));
+        }
+      }
+    } else if program.swaps.contains_key(&buffer.save_path) {
+      remove(&buffer.save_path);
+      program.swaps.remove(&buffer.save_path);
+    }
+  }
+  // closed buffers
+  for path in program.swaps.clone().keys() {
+    if !seen.contains(path) {
+      if program.swaps[path].is_some() {
+        remove(path);
+      }
+      program.swaps.remove(path);
+    }
+  }
+}
+
+/* on a clean exit */
+pub fn remove_all(program: &mut Program) {
+  for (path, owned) in program.swaps.drain() {
+    if owned.is_some() {
+      remove(&path);
+    }
+  }
+}
+
+pub fn prompt(program: &Program) -> Option<String> {
+  let path = program.recovery.first()?;
+  let modified = read(path).map(|x| x.modified).unwrap_or_default();
+  Some(format!("swap file for {} ({}): [r]estore [d]iff [x] discard [esc] later", path, modified))
+}
+
+/* the prompt is about the current buffer (or the diff of it against the swap), so r/d/x answer it */
+pub fn prompting_here(program: &Program) -> bool {
+  match (program.recovery.first(), program.buffers.get(program.current)) {
+    (Some(path), Some(buffer)) => buffer.save_path == *path || buffer.buf_name == format!("{} (swap diff)", path),
+    _ => false,
+  }
+}
+
+/* answer the recovery prompt of the first pending file; `None` leaves the swap alone */
+pub fn resolve(program: &mut Program, answer: Option<char>) {
+  let path = match program.recovery.first() {
+    Some(p) => p.clone(),
+    None => return,
+  };
+  let index = program.buffers.iter().position(|x| x.save_path == path);
+  let swap = read(&path);
+  match (answer, index, swap) {
+    (Some('r'), Some(index), Some(swap)) => {
+      program.buffers[index].lines = swap.lines;
+      program.buffers[index].cursor = (0, 0);
+      program.buffers[index].display_start_line = 0;
+      program.current = index;
+      program.swaps.insert(path.clone(), Some(0)); // ours now, replaced on the next sync
+      program.recovery.remove(0);
+      program.io = format!("restored {} from swap, write it to keep the changes", path);
+    },
+    (Some('d'), Some(index), Some(swap)) => {
+      let mut lines = vec![format!("--- {}", path), format!("+++ {} (swap, {})", path, swap.modified)];
+      lines.append(&mut crate::diff::unified(&program.buffers[index].lines, &swap.lines, 3));
+      program.buffers.push(crate::EditorBuffer {
+        cursor: (0, 0),
+        selection: ((0,0), (0,0)),
+        old_lines: lines.clone(),
+        lines,
+        buf_type: BufferType::File,
+        display_start_line: 0,
+        display_offset_collumn: 0,
+        buf_name: format!("{} (swap diff)", path), // see prompting_here
+        save_path: String::new(),
+        saved: true,
+        format: Default::default(),
+        saved_format: Default::default(),
+        large: None,
+        disk: None,
+        read_only: false,
+        changes: None,
+        git: None,
+      });
+      program.current = program.buffers.len()-1;
+      return; // still waiting for restore/discard
+    },
+    (Some('x'), _, _) | (Some(_), None, _) | (Some(_), _, None) => {
+      remove(&path);
+      program.recovery.remove(0);
+      program.io = format!("discarded swap of {}", path);
+    },
+    _ => {
+      program.swaps.insert(path.clone(), None); // don't overwrite it
+      program.recovery.remove(0);
+      program.io = format!("kept swap of {} at {}", path, swap_path(&path));
+    }
+  }
+  if let Some(prompt) = prompt(program) {
+    program.io = prompt;
+  }
+}
@@ -0,0 +1,230 @@
+/* tree explorer - a sidebar left of the buffers, directories expand in place.
+   `tree` shows/hides it, `tree_focus` moves the keys between it and the buffer */
+use std::fs;
+use crate::{Editor, KeyCode, KeyEvent, Direction, Program, Subdir, State, RGB};
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Tree {
+  pub root: String,
+  pub expanded: Vec<String>,         // expanded directories, kept while the tree is hidden
+  pub entries: Vec<(usize, Subdir)>, // (depth, entry), flattened
+  pub selected: usize,
+  pub offset: usize,                 // first entry shown
+  pub visible: bool,
+  pub focus: bool,
+  pub followed: String,              // file of the active buffer the selection was last moved to
+}
+
+impl Tree {
+  pub fn new(root: &str) -> Tree {
+    let root = fs::canonicalize(root).map(|x| x.to_string_lossy().to_string()).unwrap_or(root.to_string());
+    Tree {root, expanded: vec![], entries: vec![], selected: 0, offset: 0, visible: false, focus: false, followed: String::new()}
+  }
+}
+
+fn walk(program: &Program, tree: &Tree, path: &str, depth: usize, entries: &mut Vec<(usize, Subdir)>) {
+  let ops = &program.config.ops.explorer;
+  for subdir in crate::explorer::read(path, ops, ops.show_hidden).subdirs.into_iter().filter(|x| x.name != "..") {
+    let expanded = subdir.is_dir && tree.expanded.contains(&subdir.abs_path);
+    let abs_path = subdir.abs_path.clone();
+    entries.push((depth, subdir));
+    if expanded {
+      walk(program, tree, &abs_path, depth+1, entries);
+    }
+  }
+}
+
+/* re-read the listing, keeping the selection on the same path */
+pub fn rebuild(program: &mut Program) {
+  let mut tree = match program.tree.clone() {
+    Some(t) => t,
+    None => return,
+  };
+  let selected = tree.entries.get(tree.selected).map(|x| x.1.abs_path.clone());
+  let mut entries = vec![];
+  walk(program, &tree, &tree.root.clone(), 0, &mut entries);
+  tree.entries = entries;
+  tree.selected = selected.and_then(|s| tree.entries.iter().position(|x| x.1.abs_path == s)).unwrap_or(0);
+  program.tree = Some(tree);
+}
+
+/* `tree [root]` */
+pub fn toggle(program: &mut Program, root: Option<String>) {
+  let root = root.map(|x| crate::explorer::expand_home(&x));
+  match (&program.tree, &root) {
+    (Some(tree), None) if tree.visible => {
+      program.tree.as_mut().unwrap().visible = false;
+      program.tree.as_mut().unwrap().focus = false;
+      return;
+    },
+    (Some(_), None) => {},
+    _ => program.tree = Some(Tree::new(&root.unwrap_or(String::from(".")))),
+  }
+  let tree = program.tree.as_mut().unwrap();
+  tree.visible = true;
+  tree.focus = true;
+  tree.followed = String::new();
+  rebuild(program);
+  follow(program);
+}
+
+pub fn toggle_focus(program: &mut Program) {
+  match program.tree.as_mut() {
+    Some(tree) if tree.visible => tree.focus = !tree.focus,
+    _ => toggle(program, None),
+  }
+}
+
+/* select the active buffer's file, expanding its parents (ops.explorer.follow) */
+pub fn follow(program: &mut Program) {
+  if !program.config.ops.explorer.follow || program.buffers.is_empty() {
+    return;
+  }
+  let path = program.get_buffer().save_path.clone();
+  let path = fs::canonicalize(&path).map(|x| x.to_string_lossy().to_string()).unwrap_or(path);
+  let tree = match program.tree.as_mut() {
+    Some(t) if t.visible && t.followed != path => t,
+    _ => return,
+  };
+  tree.followed = path.clone();
+  if !path.starts_with(&(tree.root.clone() + "/")) {
+    return;
+  }
+  let mut changed = false;
+  for dir in std::path::Path::new(&path).ancestors().skip(1) {
+    let dir = dir.to_string_lossy().to_string();
+    if dir.len() <= tree.root.len() {
+      break;
+    }
+    if !tree.expanded.contains(&dir) {
+      tree.expanded.push(dir);
+      changed = true;
+    }
+  }
+  if changed {
+    rebuild(program);
+  }
+  let tree = program.tree.as_mut().unwrap();
+  if let Some(index) = tree.entries.iter().position(|x| x.1.abs_path == path) {
+    tree.selected = index;
+  }
+}
+
+/* keys while the tree has focus in Control state; false lets the key through (`:`, keybinds...) */
+pub fn key(program: &mut Program, event: &KeyEvent) -> bool {
+  let mut tree = match program.tree.clone() {
+    Some(t) if t.visible && t.focus && program.state == State::Control && event.modifiers.is_empty() => t,
+    _ => return false,
+  };
+  let entry = tree.entries.get(tree.selected).cloned();
+  match event.code {
+    KeyCode::Arrow(Direction::Up) => tree.selected = tree.selected.saturating_sub(1),
+    KeyCode::Arrow(Direction::Down) => tree.selected = std::cmp::min(tree.selected+1, tree.entries.len().saturating_sub(1)),
+    KeyCode::Enter | KeyCode::Arrow(Direction::Right) => match entry {
+      Some((_, subdir)) if subdir.is_dir => {
+        if let Some(i) = tree.expanded.iter().position(|x| *x == subdir.abs_path) {
+          if event.code == KeyCode::Enter {
+            tree.expanded.remove(i);
+          }
+        } else {
+          tree.expanded.push(subdir.abs_path);
+        }
+        program.tree = Some(tree);
+        rebuild(program);
+        return true;
+      },
+      Some((_, subdir)) => {
+        tree.focus = false;
+        program.tree = Some(tree);
+        crate::explorer::open_file(program, subdir.abs_path);
+        return true;
+      },
+      None => {},
+    },
+    KeyCode::Arrow(Direction::Left) => if let Some((depth, subdir)) = entry { // collapse, or go up to the parent directory
+      if let Some(i) = tree.expanded.iter().position(|x| *x == subdir.abs_path) {
+        tree.expanded.remove(i);
+        program.tree = Some(tree);
+        rebuild(program);
+        return true;
+      } else if depth > 0 {
+        tree.selected = tree.entries[..tree.selected].iter().rposition(|x| x.0 == depth-1).unwrap_or(0);
+      }
+    },
+    KeyCode::Char('.') => {
+      program.config.ops.explorer.show_hidden = !program.config.ops.explorer.show_hidden;
+      rebuild(program);
+      return true;
+    },
+    KeyCode::Escape => tree.focus = false,
+    _ => return false,
+  }
+  program.tree = Some(tree);
+  true
+}
+
+/* columns taken by the sidebar, 0 when hidden or the terminal is too narrow */
+pub fn width(program: &Program, terx: u16) -> u16 {
+  let width = program.config.ops.explorer.tree_width as u16;
+  match &program.tree {
+    Some(t) if t.visible && terx > width + 10 => width,
+    _ => 0,
+  }
+}
+
+/* `rows` lines of exactly `width` columns, the last one being the border */
+pub fn render(program: &mut Program, rows: usize, width: usize) -> Vec<String> {
+  let colors = program.config.colors.clone();
+  let tree = match program.tree.as_mut() {
+    Some(t) => t,
+    None => return vec![],
+  };
+  if tree.selected < tree.offset {
+    tree.offset = tree.selected;
+  } else if tree.selected >= tree.offset + rows {
+    tree.offset = tree.selected + 1 - rows;
+  }
+  let text_width = width.saturating_sub(1);
+  let mut result = vec![];
+  for row in 0..rows {
+    let (text, foreground, background) = match tree.entries.get(tree.offset + row) {
+      Some((depth, subdir)) => {
+        let marker = if !subdir.is_dir {"  "} else if tree.expanded.contains(&subdir.abs_path) {"▾ "} else {"▸ "};
+        let text = format!("{}{}{}", "  ".repeat(*depth), marker, subdir.name);
+        let foreground = if subdir.is_dir {colors.directory} else {colors.foreground};
+        let background = if tree.offset + row == tree.selected && (tree.focus || subdir.abs_path == tree.followed) {colors.selection_color} else {colors.background};
+        (text, foreground, background)
+      },
+      None => (String::new(), colors.foreground, colors.background),
+    };
+    let mut text = text.chars().take(text_width).collect::<String>();
+    text += &" ".repeat(text_width - text.chars().count());
+    let border: RGB = colors.border;
+    result.push(format!("\x1b[38;2;{foreground}m\x1b[48;2;{background}m{text}\x1b[38;2;{border}m\x1b[48;2;{}m│", colors.background));
+  }
+  result
+}
+
+/* put the sidebar in front of every row of the buffer area, restoring the colors the row started with */
+pub fn splice(area: String, sidebar: Vec<String>) -> String {
+  let mut result = String::new();
+  let (mut foreground, mut background) = (String::new(), String::new());
+  for (row, side) in area.split_inclusive('\n').zip(sidebar.iter().chain(std::iter::repeat(&String::new()))) {
+    result += side;
+    result += &foreground;
+    result += &background;
+    result += row;
+    for (i, _) in row.match_indices("\x1b[") {
+      let end = match row[i..].find('m') {
+        Some(e) => i + e + 1,
+        None => continue,
+      };
+      if row[i..].starts_with("\x1b[38;2;") {
+        foreground = row[i..end].to_string();
+      } else if row[i..].starts_with("\x1b[48;2;") {
+        background = row[i..end].to_string();
+      }
+    }
+  }
+  result
+}
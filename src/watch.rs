@@ -0,0 +1,92 @@
+/* changes on disk - buffers remember the size, mtime and hash of their file as it was read or written.
+   files are polled: clean buffers reload when their file changes, modified ones get a warning and `w` refuses
+   to overwrite the newer file (`w!` does anyway) */
+use std::{collections::hash_map::DefaultHasher, fs, hash::{Hash, Hasher}, time::SystemTime};
+use crate::{BufferType, EditorBuffer, Program, encoding, hex};
+
+pub const POLL_INTERVAL: i32 = 1000; // ms
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Stamp {
+  size: u64,
+  mtime: Option<SystemTime>,
+  hash: u64,
+  warned: bool, // the buffer was modified when the file changed, told the user already
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  bytes.hash(&mut hasher);
+  hasher.finish()
+}
+
+/* `bytes` is what `path` holds now - just read or written */
+pub fn stamp(path: &str, bytes: &[u8]) -> Option<Stamp> {
+  let metadata = fs::metadata(path).ok()?;
+  Some(Stamp {size: metadata.len(), mtime: metadata.modified().ok(), hash: hash(bytes), warned: false})
+}
+
+/* the file's contents when it differs from what the buffer was loaded from or saved as */
+fn changed(buffer: &EditorBuffer) -> Option<Vec<u8>> {
+  let stamp = buffer.disk.as_ref()?;
+  let metadata = fs::metadata(&buffer.save_path).ok()?;
+  if metadata.len() == stamp.size && metadata.modified().ok() == stamp.mtime {
+    return None;
+  }
+  let bytes = fs::read(&buffer.save_path).ok()?;
+  if hash(&bytes) == stamp.hash {
+    return None;
+  }
+  Some(bytes)
+}
+
+/* message for `w` when the file got newer contents since the buffer read it, None if writing is fine */
+pub fn conflict(buffer: &EditorBuffer) -> Option<String> {
+  changed(buffer).map(|_| format!("{} changed on disk since it was read, `w!` to overwrite it", buffer.buf_name))
+}
+
+fn reload(buffer: &mut EditorBuffer, bytes: Vec<u8>) {
+  buffer.disk = stamp(&buffer.save_path, &bytes);
+  match &buffer.buf_type {
+    BufferType::Hex(_) => {
+      buffer.lines = hex::render(&bytes);
+      buffer.buf_type = BufferType::Hex(bytes);
+    },
+    _ => {
+      let (lines, format) = encoding::decode(&bytes);
+      buffer.lines = lines;
+      buffer.saved_format = format.clone();
+      buffer.format = format;
+    },
+  }
+  buffer.old_lines = buffer.lines.clone();
+  buffer.changes = None;
+  buffer.cursor.1 = std::cmp::min(buffer.cursor.1 as usize, buffer.lines.len() - 1) as u32;
+  buffer.cursor.0 = std::cmp::min(buffer.cursor.0 as usize, buffer.lines[buffer.cursor.1 as usize].chars().count()) as u32;
+}
+
+/* called every POLL_INTERVAL and after keys, true if some buffer was reloaded or warned about */
+pub fn check(program: &mut Program) -> bool {
+  let mut messages = vec![];
+  for buffer in program.buffers.iter_mut() {
+    if !matches!(buffer.buf_type, BufferType::File | BufferType::Hex(_)) || buffer.large.is_some() {
+      continue;
+    }
+    let bytes = match changed(buffer) {
+      Some(b) => b,
+      None => continue,
+    };
+    if !buffer.modified() {
+      reload(buffer, bytes);
+      messages.push(format!("reloaded {}, it changed on disk", buffer.buf_name));
+    } else if !buffer.disk.as_ref().map(|x| x.warned).unwrap_or(true) {
+      buffer.disk.as_mut().unwrap().warned = true;
+      messages.push(format!("{} changed on disk and has unsaved changes, `w!` overwrites it", buffer.buf_name));
+    }
+  }
+  if messages.is_empty() {
+    return false;
+  }
+  program.io = messages.join("; ");
+  true
+}